@@ -0,0 +1,76 @@
+use strum_macros::{Display, EnumIter};
+
+use crate::sdl::EngineContext;
+
+/// Engine speed above which the car is assumed to be driven and active tests are refused. Cold
+/// fast idle with A/C idle-up stays well below this.
+pub const MAX_ACTUATION_RPM: u16 = 1500;
+
+/// Active tests the ECU can run on request via the Actuate header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, EnumIter, Display)]
+pub enum ActuatorTest {
+    /// Hold ignition at fixed 5° BTDC so base timing can be checked with a timing light.
+    FixedSpark,
+
+    /// Open the ISC valve to raise idle, same as A/C idle-up.
+    IdleUp,
+
+    /// Energize the radiator fan relay.
+    RadiatorFan,
+
+    /// Energize the fuel pump relay.
+    FuelPump,
+
+    /// Cut the A/C compressor clutch.
+    AcCut,
+}
+
+impl ActuatorTest {
+    /// Test number sent as first data byte of the Actuate request.
+    pub fn command(&self) -> u8 {
+        match self {
+            ActuatorTest::FixedSpark => 0x01,
+            ActuatorTest::IdleUp => 0x02,
+            ActuatorTest::RadiatorFan => 0x03,
+            ActuatorTest::FuelPump => 0x04,
+            ActuatorTest::AcCut => 0x05,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ActuatorTest::FixedSpark => "Fixed spark 5° BTDC",
+            ActuatorTest::IdleUp => "ISC idle-up",
+            ActuatorTest::RadiatorFan => "Radiator fan relay",
+            ActuatorTest::FuelPump => "Fuel pump relay",
+            ActuatorTest::AcCut => "A/C compressor cut",
+        }
+    }
+}
+
+/// Active tests are only allowed with the car standing still and the engine around idle.
+pub fn actuation_allowed(ctx: &EngineContext) -> bool {
+    ctx.vehicle_speed == 0 && ctx.engine_speed <= MAX_ACTUATION_RPM
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        actuator::{MAX_ACTUATION_RPM, actuation_allowed},
+        sdl::EngineContext,
+    };
+
+    #[test]
+    fn test_actuation_allowed() {
+        let mut ctx = EngineContext {
+            engine_speed: 850,
+            ..Default::default()
+        };
+        assert!(actuation_allowed(&ctx));
+        ctx.vehicle_speed = 1;
+        assert!(!actuation_allowed(&ctx));
+        ctx.vehicle_speed = 0;
+        ctx.engine_speed = MAX_ACTUATION_RPM + 1;
+        assert!(!actuation_allowed(&ctx));
+    }
+}
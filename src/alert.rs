@@ -0,0 +1,16 @@
+use std::ops::RangeInclusive;
+
+// Where the gauges turn red or blink. Freeze frames are captured on the same values, see
+// `freeze_frame::Trigger`.
+
+/// ECT at or over this is overheating, in C.
+pub const OVERHEAT_COOLANT_TEMP: i8 = 110;
+
+/// ECU supply voltage while the engine runs, anything outside is a charging fault.
+pub const CHARGING_VOLTAGE: RangeInclusive<f32> = 13.1..=15.2;
+
+/// Lugging is a load at or over [`LUGGING_LOAD`] below this engine speed.
+pub const LUGGING_RPM: u16 = 2500;
+
+/// Calculated load in % that counts as lugging below [`LUGGING_RPM`].
+pub const LUGGING_LOAD: u8 = 85;
@@ -0,0 +1,189 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    connection::{Connector, SerialConnector, TcpConnector},
+    discovery::DiscoveringConnector,
+};
+
+/// SDL runs at a non-standard 7812 baud.
+pub const SDL_BAUD_RATE: u32 = 7812;
+
+/// Port setting that finds the cable by probing every USB serial port, see
+/// [`DiscoveringConnector`].
+pub const AUTO_PORT: &str = "auto";
+
+/// Port setting prefix for talking SDL over TCP instead, e.g. `tcp:127.0.0.1:3500` for the
+/// emulator served by `--emulate`.
+pub const TCP_PORT_PREFIX: &str = "tcp:";
+
+/// Connection settings. Every field is optional in the file, anything left out keeps its default.
+///
+/// ```toml
+/// port = "auto"
+/// baud_rate = 7812
+/// read_timeout_ms = 1000
+/// request_delay_ms = 0
+/// vehicle_profile = "/home/dietpi/baleno.toml"
+/// record_dir = "/home/dietpi/suzui-logs"
+/// record_max_file_size = 4194304
+/// record_max_files = 100
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Serial device of the KKL cable, [`AUTO_PORT`] to look for it, or a `host:port` after
+    /// [`TCP_PORT_PREFIX`].
+    pub port: String,
+
+    pub baud_rate: u32,
+
+    /// How long to wait for the ECU to answer before giving up on a request.
+    pub read_timeout_ms: u64,
+
+    /// Minimum gap between the end of one request and the start of the next, for ECUs or cables
+    /// that choke on back-to-back requests.
+    pub request_delay_ms: u64,
+
+    /// Vehicle profile file, see [`crate::vehicle::VehicleProfile`]. Baleno values if not set.
+    pub vehicle_profile: Option<PathBuf>,
+
+    /// Directory to log every poll's raw data to, see [`crate::recorder::Recorder`]. Nothing is
+    /// recorded if not set.
+    pub record_dir: Option<PathBuf>,
+
+    /// Log file size in bytes after which the next file is started.
+    pub record_max_file_size: u64,
+
+    /// Log files kept, the oldest are deleted beyond this.
+    pub record_max_files: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: AUTO_PORT.to_string(),
+            baud_rate: SDL_BAUD_RATE,
+            read_timeout_ms: 1000,
+            request_delay_ms: 0,
+            vehicle_profile: None,
+            record_dir: None,
+            record_max_file_size: 4 * 1024 * 1024,
+            record_max_files: 100,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "can't read config: {err}"),
+            ConfigError::Parse(err) => write!(f, "invalid config: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Read config from a TOML file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml(&contents)
+    }
+
+    /// Read config from a TOML file if there is one, defaults otherwise.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        match Self::load(path) {
+            Err(ConfigError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(ConfigError::Parse)
+    }
+
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_millis(self.read_timeout_ms)
+    }
+
+    pub fn request_delay(&self) -> Duration {
+        Duration::from_millis(self.request_delay_ms)
+    }
+
+    /// Connector for the configured port, discovering it if set to [`AUTO_PORT`].
+    pub fn connector(&self) -> Box<dyn Connector> {
+        if self.port == AUTO_PORT {
+            Box::new(DiscoveringConnector::new(
+                self.baud_rate,
+                self.read_timeout(),
+            ))
+        } else if let Some(addr) = self.port.strip_prefix(TCP_PORT_PREFIX) {
+            Box::new(TcpConnector {
+                addr: addr.to_string(),
+                timeout: self.read_timeout(),
+            })
+        } else {
+            Box::new(self.serial_connector())
+        }
+    }
+
+    pub fn serial_connector(&self) -> SerialConnector {
+        SerialConnector {
+            path: self.port.clone(),
+            baud_rate: self.baud_rate,
+            timeout: self.read_timeout(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, ConfigError};
+
+    #[test]
+    fn test_missing_fields_keep_defaults() {
+        let config = Config::from_toml("port = \"/dev/ttyACM0\"\nrequest_delay_ms = 20").unwrap();
+        assert_eq!(config.port, "/dev/ttyACM0");
+        assert_eq!(config.request_delay_ms, 20);
+        assert_eq!(config.baud_rate, 7812);
+        assert_eq!(config.read_timeout_ms, 1000);
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_invalid_config_rejected() {
+        assert!(matches!(
+            Config::from_toml("baud = 9600"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("baud_rate = \"fast\""),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let config = Config::load_or_default("/nonexistent/suzui-rs.toml").unwrap();
+        assert_eq!(config, Config::default());
+        assert!(matches!(
+            Config::load("/nonexistent/suzui-rs.toml"),
+            Err(ConfigError::Io(_))
+        ));
+    }
+}
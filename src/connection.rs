@@ -0,0 +1,154 @@
+use std::{
+    fmt::Debug,
+    io,
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use strum_macros::Display;
+
+use crate::transport::{MockTransport, Transport};
+
+/// Consecutive failed polls after which the ECU is considered lost. A single timeout is common
+/// enough on the K-line (cranking, electrical noise) that it should not blank the dashboard.
+pub const MAX_POLL_FAILURES: u32 = 3;
+
+/// Where the link to the ECU stands.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Display)]
+pub enum ConnectionState {
+    /// No port open, e.g. cable not plugged in.
+    Disconnected,
+
+    /// Port open, waiting for the ECU to answer an ID request.
+    Probing,
+
+    /// ECU answered, data is flowing.
+    Streaming,
+
+    /// ECU was streaming but stopped answering, e.g. ignition off. Probed again with backoff.
+    Lost,
+}
+
+impl ConnectionState {
+    /// Whether the values on screen are live.
+    pub fn is_streaming(&self) -> bool {
+        *self == ConnectionState::Streaming
+    }
+}
+
+/// Exponential backoff between connection attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+    next_attempt: Option<Instant>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_secs(8))
+    }
+}
+
+impl Backoff {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            current: min,
+            next_attempt: None,
+        }
+    }
+
+    /// Whether enough time has passed since the last failure to try again.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next_attempt.is_none_or(|next| now >= next)
+    }
+
+    /// Schedule the next attempt after a failure, doubling the wait each time.
+    pub fn failed(&mut self, now: Instant) {
+        self.next_attempt = Some(now + self.current);
+        self.current = (self.current * 2).min(self.max);
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.min;
+        self.next_attempt = None;
+    }
+}
+
+/// Opens transports to the ECU. Called again whenever the port goes away.
+pub trait Connector: Debug + Send {
+    fn open(&mut self) -> io::Result<Box<dyn Transport>>;
+}
+
+/// Serial device, i.e. the VAG KKL cable.
+#[derive(Debug, Clone)]
+pub struct SerialConnector {
+    pub path: String,
+    pub baud_rate: u32,
+    pub timeout: Duration,
+}
+
+impl Connector for SerialConnector {
+    fn open(&mut self) -> io::Result<Box<dyn Transport>> {
+        let port = serialport::new(&self.path, self.baud_rate)
+            .timeout(self.timeout)
+            .open_native()?;
+        Ok(Box::new(port))
+    }
+}
+
+/// ECU reachable over TCP, e.g. a serial-to-network bridge or the ECU emulator.
+#[derive(Debug, Clone)]
+pub struct TcpConnector {
+    pub addr: String,
+    pub timeout: Duration,
+}
+
+impl Connector for TcpConnector {
+    fn open(&mut self) -> io::Result<Box<dyn Transport>> {
+        let stream = TcpStream::connect(&self.addr)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_nodelay(true)?;
+        Ok(Box::new(stream))
+    }
+}
+
+/// Reopening a mock hands out another handle to the same scripted state.
+impl Connector for MockTransport {
+    fn open(&mut self) -> io::Result<Box<dyn Transport>> {
+        Ok(Box::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::connection::Backoff;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(4));
+        let now = Instant::now();
+        assert!(backoff.is_due(now));
+
+        backoff.failed(now);
+        assert!(!backoff.is_due(now));
+        assert!(backoff.is_due(now + Duration::from_secs(1)));
+
+        backoff.failed(now);
+        assert!(!backoff.is_due(now + Duration::from_secs(1)));
+        assert!(backoff.is_due(now + Duration::from_secs(2)));
+
+        backoff.failed(now);
+        backoff.failed(now);
+        assert!(!backoff.is_due(now + Duration::from_secs(3)));
+        assert!(backoff.is_due(now + Duration::from_secs(4)));
+
+        backoff.reset();
+        assert!(backoff.is_due(now));
+    }
+}
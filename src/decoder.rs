@@ -0,0 +1,175 @@
+use crate::{
+    error::SdlError,
+    sdl::{SdlHeader, SdlMessage},
+};
+
+/// Smallest possible frame: header, length and checksum.
+const MIN_FRAME_LEN: usize = 3;
+
+/// Incremental SDL frame decoder. Bytes can be pushed in arbitrary chunks as they come off the
+/// wire, frame boundaries are found from the header and length bytes and confirmed by the
+/// checksum. Anything that can't be the start of a valid frame (line noise, a dropped byte
+/// somewhere in the middle of a frame) is thrown away one byte at a time until the stream lines
+/// up again.
+#[derive(Debug, Default)]
+pub struct SdlFrameDecoder {
+    buf: Vec<u8>,
+    discarded: usize,
+    rejection: Option<SdlError>,
+}
+
+impl SdlFrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed bytes read from the line.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Drop everything buffered, e.g. before a new request.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.rejection = None;
+    }
+
+    /// Why the first bytes thrown away since the last [`Self::clear`] were not a frame. Tells a
+    /// mangled response apart from no response at all once the line goes quiet.
+    pub fn take_rejection(&mut self) -> Option<SdlError> {
+        self.rejection.take()
+    }
+
+    /// Bytes thrown away so far while looking for frame boundaries.
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+
+    /// Bytes received but not yet part of a complete frame.
+    pub fn pending(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Next complete frame, or `None` until more bytes are pushed.
+    pub fn next_frame(&mut self) -> Option<SdlMessage> {
+        loop {
+            let start = self
+                .buf
+                .iter()
+                .position(|b| SdlHeader::from_repr(*b as usize).is_some());
+            let start = start.unwrap_or(self.buf.len());
+            if start > 0 {
+                self.reject(SdlError::BadHeader(self.buf[0]));
+                self.discard(start);
+            }
+            let length = *self.buf.get(1)? as usize;
+            if length < MIN_FRAME_LEN {
+                self.reject(SdlError::TruncatedFrame {
+                    expected: MIN_FRAME_LEN,
+                    actual: length,
+                });
+                self.discard(1);
+                continue;
+            }
+            if self.buf.len() < length {
+                // A garbage byte that looks like a header can claim a long frame. If a complete
+                // frame already sits behind it, the claim was bogus since frames never overlap.
+                match (1..self.buf.len()).find(|&i| self.frame_at(i).is_some()) {
+                    Some(i) => {
+                        self.discard(i);
+                        continue;
+                    }
+                    None => return None,
+                }
+            }
+            match SdlMessage::try_from(&self.buf[..length]) {
+                Ok(frame) => {
+                    self.buf.drain(..length);
+                    return Some(frame);
+                }
+                Err(err) => {
+                    self.reject(err);
+                    self.discard(1);
+                }
+            }
+        }
+    }
+
+    /// Valid complete frame starting at `start`, if there is one.
+    fn frame_at(&self, start: usize) -> Option<SdlMessage> {
+        let bytes = &self.buf[start..];
+        let length = *bytes.get(1)? as usize;
+        SdlMessage::try_from(bytes.get(..length)?).ok()
+    }
+
+    fn reject(&mut self, err: SdlError) {
+        self.rejection.get_or_insert(err);
+    }
+
+    fn discard(&mut self, count: usize) {
+        self.buf.drain(..count);
+        self.discarded += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        decoder::SdlFrameDecoder,
+        sdl::{SdlHeader, SdlMessage},
+    };
+
+    fn data_frame(values: &[u8]) -> Vec<u8> {
+        SdlMessage::new(SdlHeader::Data, Some(values.to_vec())).to_bytes()
+    }
+
+    #[test]
+    fn test_split_across_chunks() {
+        let frame = data_frame(&[0x04, 0x05, 0x08]);
+        let mut decoder = SdlFrameDecoder::new();
+        for byte in &frame[..frame.len() - 1] {
+            decoder.push(&[*byte]);
+            assert!(decoder.next_frame().is_none());
+        }
+        decoder.push(&frame[frame.len() - 1..]);
+        assert_eq!(decoder.next_frame().unwrap().to_bytes(), frame);
+        assert!(decoder.pending().is_empty());
+    }
+
+    #[test]
+    fn test_multiple_frames_of_any_length() {
+        let id = SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])).to_bytes();
+        let ack = SdlMessage::new(SdlHeader::Actuate, Some(vec![0x01, 0x01])).to_bytes();
+        let data = data_frame(&[0; 17]);
+        let mut decoder = SdlFrameDecoder::new();
+        decoder.push(&[id.clone(), data.clone(), ack.clone()].concat());
+        assert_eq!(decoder.next_frame().unwrap().to_bytes(), id);
+        assert_eq!(decoder.next_frame().unwrap().to_bytes(), data);
+        assert_eq!(decoder.next_frame().unwrap().to_bytes(), ack);
+        assert!(decoder.next_frame().is_none());
+    }
+
+    #[test]
+    fn test_resync_after_garbage() {
+        let frame = data_frame(&[0x10, 0x13, 0x15]);
+        let mut decoder = SdlFrameDecoder::new();
+        // noise, including bytes that look like headers
+        decoder.push(&[0x00, 0xff, 0x13, 0x02, 0x10, 0x7f, 0x55]);
+        decoder.push(&frame);
+        assert_eq!(decoder.next_frame().unwrap().to_bytes(), frame);
+        assert_eq!(decoder.discarded(), 7);
+    }
+
+    #[test]
+    fn test_resync_after_dropped_byte() {
+        let first = data_frame(&[0x01, 0x02, 0x03]);
+        let second = data_frame(&[0x04, 0x05, 0x06]);
+        let mut damaged = first.clone();
+        damaged.remove(3);
+        let mut decoder = SdlFrameDecoder::new();
+        decoder.push(&damaged);
+        decoder.push(&second);
+        assert_eq!(decoder.next_frame().unwrap().to_bytes(), second);
+        assert!(decoder.next_frame().is_none());
+    }
+}
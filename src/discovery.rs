@@ -0,0 +1,188 @@
+use std::{fmt::Debug, io, time::Duration};
+
+use serialport::{SerialPortInfo, SerialPortType};
+
+use crate::{
+    connection::{Connector, SerialConnector},
+    error::SdlError,
+    sdl::SuzukiSdlViewer,
+    transport::Transport,
+};
+
+/// A serial port found on the system and whether an ECU answered on it.
+#[derive(Debug)]
+pub struct ProbedPort {
+    pub path: String,
+    /// What the port is, e.g. `USB 0403:6001 FTDI FT232R USB UART`.
+    pub description: String,
+    pub ecu_id: Result<[u8; 2], SdlError>,
+}
+
+/// USB serial ports, which is what KKL cables show up as. Built-in UARTs (e.g. the Pi's
+/// ttyAMA0) and Bluetooth ports are left alone.
+pub fn usb_ports() -> io::Result<Vec<SerialPortInfo>> {
+    let mut ports: Vec<SerialPortInfo> = serialport::available_ports()?
+        .into_iter()
+        .filter(|port| matches!(port.port_type, SerialPortType::UsbPort(_)))
+        .collect();
+    ports.sort_by(|a, b| a.port_name.cmp(&b.port_name));
+    Ok(ports)
+}
+
+pub fn describe(port: &SerialPortInfo) -> String {
+    match &port.port_type {
+        SerialPortType::UsbPort(usb) => {
+            let mut description = format!("USB {:04x}:{:04x}", usb.vid, usb.pid);
+            for name in [&usb.manufacturer, &usb.product].into_iter().flatten() {
+                description.push(' ');
+                description.push_str(name);
+            }
+            description
+        }
+        SerialPortType::PciPort => "PCI".to_string(),
+        SerialPortType::BluetoothPort => "Bluetooth".to_string(),
+        SerialPortType::Unknown => "unknown".to_string(),
+    }
+}
+
+/// Open a port and ask for the ECU ID. The port is closed again afterwards.
+pub fn probe(connector: &mut dyn Connector) -> Result<[u8; 2], SdlError> {
+    let transport = connector.open()?;
+    let mut viewer = SuzukiSdlViewer::with_transport(transport);
+    viewer.connect()?;
+    viewer
+        .ecu_id
+        .ok_or(SdlError::PortGone(io::ErrorKind::NotConnected))
+}
+
+/// Probe every USB serial port with the given settings.
+pub fn probe_usb_ports(baud_rate: u32, timeout: Duration) -> io::Result<Vec<ProbedPort>> {
+    let probed = usb_ports()?
+        .iter()
+        .map(|port| {
+            let mut connector = SerialConnector {
+                path: port.port_name.clone(),
+                baud_rate,
+                timeout,
+            };
+            ProbedPort {
+                path: port.port_name.clone(),
+                description: describe(port),
+                ecu_id: probe(&mut connector),
+            }
+        })
+        .collect();
+    Ok(probed)
+}
+
+/// Index of the first candidate an ECU answers on, candidates after it are not touched.
+fn first_answering(candidates: &mut [&mut dyn Connector]) -> Option<usize> {
+    candidates
+        .iter_mut()
+        .position(|connector| probe(*connector).is_ok())
+}
+
+/// Connector that finds the KKL cable by probing every USB serial port with an ECU ID request
+/// and opens the one that answers. The port found last time is tried first on reconnect.
+#[derive(Debug, Clone)]
+pub struct DiscoveringConnector {
+    pub baud_rate: u32,
+    pub timeout: Duration,
+    found: Option<String>,
+}
+
+impl DiscoveringConnector {
+    pub fn new(baud_rate: u32, timeout: Duration) -> Self {
+        Self {
+            baud_rate,
+            timeout,
+            found: None,
+        }
+    }
+
+    /// Port the ECU answered on last, if any.
+    pub fn found(&self) -> Option<&str> {
+        self.found.as_deref()
+    }
+}
+
+impl Connector for DiscoveringConnector {
+    fn open(&mut self) -> io::Result<Box<dyn Transport>> {
+        let mut paths: Vec<String> = usb_ports()?
+            .into_iter()
+            .map(|port| port.port_name)
+            .collect();
+        if let Some(found) = &self.found
+            && let Some(idx) = paths.iter().position(|path| path == found)
+        {
+            let found = paths.remove(idx);
+            paths.insert(0, found);
+        }
+        let mut connectors: Vec<SerialConnector> = paths
+            .into_iter()
+            .map(|path| SerialConnector {
+                path,
+                baud_rate: self.baud_rate,
+                timeout: self.timeout,
+            })
+            .collect();
+        let mut candidates: Vec<&mut dyn Connector> = connectors
+            .iter_mut()
+            .map(|connector| connector as &mut dyn Connector)
+            .collect();
+        let Some(idx) = first_answering(&mut candidates) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no ECU answered on any USB serial port",
+            ));
+        };
+        let connector = &mut connectors[idx];
+        self.found = Some(connector.path.clone());
+        connector.open()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::{
+        connection::Connector,
+        discovery::{first_answering, probe},
+        error::SdlError,
+        sdl::{SdlHeader, SdlMessage},
+        transport::{MockTransport, UnpluggedConnector},
+    };
+
+    fn answering_ecu() -> MockTransport {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])).to_bytes());
+        mock
+    }
+
+    #[test]
+    fn test_probe() {
+        assert_eq!(probe(&mut answering_ecu()), Ok([0x31, 0x09]));
+        assert_eq!(probe(&mut MockTransport::new()), Err(SdlError::Timeout));
+        assert_eq!(
+            probe(&mut UnpluggedConnector),
+            Err(SdlError::PortGone(io::ErrorKind::NotFound))
+        );
+    }
+
+    #[test]
+    fn test_first_answering_port_picked() {
+        let mut unplugged = UnpluggedConnector;
+        let mut silent = MockTransport::new();
+        let mut ecu = answering_ecu();
+        let mut untouched = answering_ecu();
+        let mut candidates: Vec<&mut dyn Connector> =
+            vec![&mut unplugged, &mut silent, &mut ecu, &mut untouched];
+        assert_eq!(first_answering(&mut candidates), Some(2));
+        assert_eq!(silent.written().len(), 1);
+        assert!(untouched.written().is_empty());
+
+        let mut silent = MockTransport::new();
+        assert_eq!(first_answering(&mut [&mut silent]), None);
+    }
+}
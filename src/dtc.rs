@@ -0,0 +1,273 @@
+use std::time::{Duration, Instant};
+
+use strum_macros::EnumIter;
+
+use crate::sdl::ObdAddress;
+
+/// Suzuki diagnostic trouble codes. Numbers are the 2 digit codes the ECU flashes on the CHECK
+/// ENGINE light with the diag. switch grounded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, EnumIter)]
+pub enum FaultCode {
+    OxygenSensor,
+    EctHigh,
+    EctLow,
+    TpsHigh,
+    TpsLow,
+    IatHigh,
+    VehicleSpeedSensor,
+    IatLow,
+    MapHigh,
+    MapLow,
+    IgnitionSignal,
+    CamshaftPosition,
+    CrankshaftPosition,
+    ClosedThrottleSwitch,
+    IdleSpeedControl,
+    Egr,
+    Injector,
+}
+
+impl FaultCode {
+    /// Flash code number.
+    pub fn code(&self) -> u8 {
+        match self {
+            FaultCode::OxygenSensor => 13,
+            FaultCode::EctHigh => 14,
+            FaultCode::EctLow => 15,
+            FaultCode::TpsHigh => 21,
+            FaultCode::TpsLow => 22,
+            FaultCode::IatHigh => 23,
+            FaultCode::VehicleSpeedSensor => 24,
+            FaultCode::IatLow => 25,
+            FaultCode::MapHigh => 31,
+            FaultCode::MapLow => 32,
+            FaultCode::IgnitionSignal => 41,
+            FaultCode::CamshaftPosition => 42,
+            FaultCode::CrankshaftPosition => 43,
+            FaultCode::ClosedThrottleSwitch => 44,
+            FaultCode::IdleSpeedControl => 46,
+            FaultCode::Egr => 51,
+            FaultCode::Injector => 52,
+        }
+    }
+
+    /// Short description of the fault. "High"/"low" refer to the sensor voltage, so a high ECT
+    /// voltage reads as a cold engine.
+    pub fn description(&self) -> &'static str {
+        match self {
+            FaultCode::OxygenSensor => "O2 sensor",
+            FaultCode::EctHigh => "ECT high",
+            FaultCode::EctLow => "ECT low",
+            FaultCode::TpsHigh => "TPS high",
+            FaultCode::TpsLow => "TPS low",
+            FaultCode::IatHigh => "IAT high",
+            FaultCode::VehicleSpeedSensor => "VSS",
+            FaultCode::IatLow => "IAT low",
+            FaultCode::MapHigh => "MAP high",
+            FaultCode::MapLow => "MAP low",
+            FaultCode::IgnitionSignal => "Ignition signal",
+            FaultCode::CamshaftPosition => "CMP sensor",
+            FaultCode::CrankshaftPosition => "CKP sensor",
+            FaultCode::ClosedThrottleSwitch => "CTP switch",
+            FaultCode::IdleSpeedControl => "IAC valve",
+            FaultCode::Egr => "EGR",
+            FaultCode::Injector => "Injector",
+        }
+    }
+}
+
+impl std::fmt::Display for FaultCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code(), self.description())
+    }
+}
+
+/// Fault bytes holding codes that are active right now.
+pub const CURRENT_FAULT_ADDRESSES: [ObdAddress; 3] = [
+    ObdAddress::FaultCodes1,
+    ObdAddress::FaultCodes2,
+    ObdAddress::FaultCodes3,
+];
+
+/// Fault bytes holding codes stored in memory since the last clear.
+pub const HISTORY_FAULT_ADDRESSES: [ObdAddress; 3] = [
+    ObdAddress::FaultCodes4,
+    ObdAddress::FaultCodes5,
+    ObdAddress::FaultCodes6,
+];
+
+/// Bit layout of the fault bytes, LSB first. Current and history bytes share the same layout.
+const FAULT_CODE_BITS: [[Option<FaultCode>; 8]; 3] = [
+    [
+        Some(FaultCode::OxygenSensor),
+        Some(FaultCode::EctHigh),
+        Some(FaultCode::EctLow),
+        Some(FaultCode::TpsHigh),
+        Some(FaultCode::TpsLow),
+        Some(FaultCode::IatHigh),
+        Some(FaultCode::IatLow),
+        Some(FaultCode::VehicleSpeedSensor),
+    ],
+    [
+        Some(FaultCode::MapHigh),
+        Some(FaultCode::MapLow),
+        Some(FaultCode::IgnitionSignal),
+        Some(FaultCode::CrankshaftPosition),
+        Some(FaultCode::CamshaftPosition),
+        Some(FaultCode::IdleSpeedControl),
+        Some(FaultCode::Injector),
+        None,
+    ],
+    [
+        Some(FaultCode::ClosedThrottleSwitch),
+        Some(FaultCode::Egr),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    ],
+];
+
+/// Decode a set of three fault bytes into the codes whose bits are set.
+pub fn decode_fault_bytes(bytes: [u8; 3]) -> Vec<FaultCode> {
+    let mut codes = Vec::new();
+    for (byte, layout) in bytes.iter().zip(FAULT_CODE_BITS.iter()) {
+        for (bit, code) in layout.iter().enumerate() {
+            if let Some(code) = code
+                && byte & (1 << bit) != 0
+            {
+                codes.push(*code);
+            }
+        }
+    }
+    codes.sort_by_key(|code| code.code());
+    codes
+}
+
+/// Inverse of [`decode_fault_bytes`], e.g. for an emulated ECU.
+pub fn encode_fault_bytes(codes: &[FaultCode]) -> [u8; 3] {
+    let mut bytes = [0u8; 3];
+    for (byte, layout) in bytes.iter_mut().zip(FAULT_CODE_BITS.iter()) {
+        for (bit, code) in layout.iter().enumerate() {
+            if code.is_some_and(|code| codes.contains(&code)) {
+                *byte |= 1 << bit;
+            }
+        }
+    }
+    bytes
+}
+
+/// Decoded fault codes split into current and history codes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticTroubleCodes {
+    /// Faults the ECU sees right now.
+    pub current: Vec<FaultCode>,
+
+    /// Faults stored in ECU memory. May have cleared up since, e.g. intermittent wiring issues.
+    pub history: Vec<FaultCode>,
+}
+
+impl DiagnosticTroubleCodes {
+    pub fn from_raw(current: [u8; 3], history: [u8; 3]) -> Self {
+        Self {
+            current: decode_fault_bytes(current),
+            history: decode_fault_bytes(history),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current.is_empty() && self.history.is_empty()
+    }
+}
+
+/// Countdown shown at boot when history codes are present. Gives a chance to note the codes
+/// before they get cleared, useful for intermittent issues that set a code and then go away.
+#[derive(Debug, Clone)]
+pub struct ClearCodesCountdown {
+    pub codes: Vec<FaultCode>,
+    started: Instant,
+    duration: Duration,
+}
+
+impl ClearCodesCountdown {
+    pub fn new(codes: Vec<FaultCode>, duration: Duration) -> Self {
+        Self {
+            codes,
+            started: Instant::now(),
+            duration,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.started.elapsed())
+    }
+
+    pub fn is_elapsed(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::dtc::{
+        ClearCodesCountdown, DiagnosticTroubleCodes, FaultCode, decode_fault_bytes,
+        encode_fault_bytes,
+    };
+
+    #[test]
+    fn test_no_faults() {
+        assert!(decode_fault_bytes([0, 0, 0]).is_empty());
+        assert!(DiagnosticTroubleCodes::from_raw([0, 0, 0], [0, 0, 0]).is_empty());
+    }
+
+    #[test]
+    fn test_decode_bits() {
+        assert_eq!(
+            decode_fault_bytes([0b0000_0010, 0, 0]),
+            vec![FaultCode::EctHigh]
+        );
+        assert_eq!(
+            decode_fault_bytes([0b1000_0000, 0b0001_1000, 0b0000_0001]),
+            vec![
+                FaultCode::VehicleSpeedSensor,
+                FaultCode::CamshaftPosition,
+                FaultCode::CrankshaftPosition,
+                FaultCode::ClosedThrottleSwitch,
+            ]
+        );
+        // unassigned bits are ignored
+        assert!(decode_fault_bytes([0, 0b1000_0000, 0b1111_1100]).is_empty());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let codes = vec![
+            FaultCode::EctHigh,
+            FaultCode::CrankshaftPosition,
+            FaultCode::Egr,
+        ];
+        assert_eq!(decode_fault_bytes(encode_fault_bytes(&codes)), codes);
+        assert_eq!(encode_fault_bytes(&[]), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_current_vs_history() {
+        let dtcs = DiagnosticTroubleCodes::from_raw([0, 0b0010_0000, 0], [0b0000_0100, 0, 0]);
+        assert_eq!(dtcs.current, vec![FaultCode::IdleSpeedControl]);
+        assert_eq!(dtcs.history, vec![FaultCode::EctLow]);
+        assert_eq!(dtcs.current[0].to_string(), "46 IAC valve");
+    }
+
+    #[test]
+    fn test_clear_codes_countdown() {
+        let countdown = ClearCodesCountdown::new(vec![FaultCode::EctLow], Duration::from_secs(30));
+        assert!(!countdown.is_elapsed());
+        assert!(countdown.remaining() > Duration::from_secs(29));
+        let countdown = ClearCodesCountdown::new(vec![FaultCode::EctLow], Duration::ZERO);
+        assert!(countdown.is_elapsed());
+    }
+}
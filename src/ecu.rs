@@ -0,0 +1,133 @@
+use crate::sdl::ObdAddress;
+
+/// Conversion constants and supported addresses for a family of ECUs. Raw values are scaled
+/// linearly, ranges are what raw 0 and 255 map to.
+#[derive(Debug, PartialEq)]
+pub struct ParameterProfile {
+    pub engine_code: &'static str,
+
+    /// Live addresses the ECU answers, anything else is never polled.
+    pub addresses: &'static [ObdAddress],
+
+    /// RPM high/low byte divisor.
+    pub rpm_divisor: f32,
+
+    /// RPM per raw unit of desired idle.
+    pub desired_idle_scale: f32,
+
+    /// ECT/IAT range in C.
+    pub temp_range: (f32, f32),
+
+    /// Milliseconds per raw unit of injector pulse width.
+    pub inj_pw_scale: f32,
+
+    /// Ignition advance range in degrees BTDC.
+    pub ignition_advance_range: (f32, f32),
+
+    /// MAP/BARO range in kPa.
+    pub pressure_range: (f32, f32),
+
+    /// Volts per raw unit of battery voltage.
+    pub battery_voltage_scale: f32,
+
+    /// Throttle angle at raw 255, in degrees.
+    pub throttle_angle_max: f32,
+}
+
+impl ParameterProfile {
+    pub fn supports(&self, addr: ObdAddress) -> bool {
+        self.addresses.contains(&addr)
+    }
+}
+
+/// Baleno 1.3, the car this was made for. Also what is assumed for unknown ECUs.
+pub const G13BB: ParameterProfile = ParameterProfile {
+    engine_code: "G13BB",
+    addresses: &[
+        ObdAddress::RpmHigh,
+        ObdAddress::RpmLow,
+        ObdAddress::TargetIdle,
+        ObdAddress::VehicleSpeedSensor,
+        ObdAddress::EngineCoolantTemperature,
+        ObdAddress::IntakeAirTemperature,
+        ObdAddress::TpsAngle,
+        ObdAddress::TpsVoltage,
+        ObdAddress::InjectorPulseWidthHigh,
+        ObdAddress::InjectorPulseWidthLow,
+        ObdAddress::IgnitionAdvance,
+        ObdAddress::ManifoldAbsolutePressure,
+        ObdAddress::BarometricPressure,
+        ObdAddress::IscFlowDuty,
+        ObdAddress::BatteryVoltage,
+        ObdAddress::RadiatorFan,
+        ObdAddress::StatusFlags,
+    ],
+    rpm_divisor: 5.1,
+    desired_idle_scale: 7.84375,
+    temp_range: (-40.0, 119.0),
+    inj_pw_scale: 0.002,
+    ignition_advance_range: (-12.0, 78.0),
+    pressure_range: (-20.0, 146.63),
+    battery_voltage_scale: 0.0787,
+    throttle_angle_max: 125.0,
+};
+
+/// An ECU recognised by the ID it answers with.
+#[derive(Debug, PartialEq)]
+pub struct KnownEcu {
+    pub id: [u8; 2],
+    pub model: &'static str,
+    pub profile: &'static ParameterProfile,
+}
+
+/// ECU IDs with a known profile, each read off a real car. An ECU that isn't listed here is
+/// shown with [`G13BB`] values and a warning along with its ID, which is what goes in a new
+/// entry. None have been confirmed yet.
+pub const KNOWN_ECUS: &[KnownEcu] = &[];
+
+pub fn lookup(id: [u8; 2]) -> Option<&'static KnownEcu> {
+    KNOWN_ECUS.iter().find(|ecu| ecu.id == id)
+}
+
+/// ECU ID the way it's shown, e.g. `0x3109` in the unknown ECU warning.
+pub fn format_ecu_id(id: [u8; 2]) -> String {
+    format!("{:#06x}", u16::from_be_bytes(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        ecu::{G13BB, KNOWN_ECUS, format_ecu_id, lookup},
+        sdl::ObdAddress,
+    };
+
+    #[test]
+    fn test_lookup() {
+        // a real ECU answering zeros is not the simulator
+        assert_eq!(lookup([0x00, 0x00]), None);
+        assert_eq!(lookup([0xde, 0xad]), None);
+
+        // every ID once
+        for (i, ecu) in KNOWN_ECUS.iter().enumerate() {
+            assert_eq!(lookup(ecu.id), Some(&KNOWN_ECUS[i]));
+        }
+    }
+
+    #[test]
+    fn test_format_ecu_id() {
+        assert_eq!(format_ecu_id([0x31, 0x09]), "0x3109");
+        assert_eq!(format_ecu_id([0x00, 0x00]), "0x0000");
+        assert_eq!(format_ecu_id([0xab, 0xcd]), "0xabcd");
+    }
+
+    #[test]
+    fn test_profiles_list_live_addresses_only() {
+        for ecu in KNOWN_ECUS {
+            assert!(ecu.profile.addresses.iter().all(|v| !v.is_fault_code()));
+        }
+        let live = ObdAddress::iter().filter(|v| !v.is_fault_code()).count();
+        assert_eq!(G13BB.addresses.len(), live);
+    }
+}
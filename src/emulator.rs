@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, Read, Write},
+    net::TcpListener,
+    time::Duration,
+};
+
+use serialport::{SerialPort, TTYPort};
+
+use crate::{
+    decoder::SdlFrameDecoder,
+    dtc::{CURRENT_FAULT_ADDRESSES, FaultCode, HISTORY_FAULT_ADDRESSES, encode_fault_bytes},
+    ecu::{G13BB, KnownEcu},
+    sdl::{ObdAddress, SdlHeader, SdlMessage},
+};
+
+/// What `--simulate` and the emulator answer an ID request with by default. Only recognised by
+/// viewers that were given it in [`crate::sdl::SuzukiSdlViewer::extra_ecus`], so a real ECU
+/// answering zeros isn't taken for the simulator.
+pub const SIMULATOR_ECU: KnownEcu = KnownEcu {
+    id: [0x00, 0x00],
+    model: "Simulator",
+    profile: &G13BB,
+};
+
+/// Fake Suzuki ECU answering SDL requests from a programmable memory of 256 addresses. Used
+/// behind [`crate::transport::SimulatedTransport`], or served on a pseudo-terminal or TCP
+/// socket to exercise the real port code without a car.
+#[derive(Debug, Clone)]
+pub struct EcuEmulator {
+    pub ecu_id: [u8; 2],
+
+    /// What Data requests read, indexed by address.
+    pub memory: [u8; 256],
+
+    /// Commands of active tests switched on and not off again.
+    pub active_tests: BTreeSet<u8>,
+}
+
+impl Default for EcuEmulator {
+    fn default() -> Self {
+        Self::new(SIMULATOR_ECU.id)
+    }
+}
+
+impl EcuEmulator {
+    /// ECU with all-zero memory.
+    pub fn new(ecu_id: [u8; 2]) -> Self {
+        Self {
+            ecu_id,
+            memory: [0; 256],
+            active_tests: BTreeSet::new(),
+        }
+    }
+
+    /// Warm engine idling at 800 RPM, throttle closed, no fault codes.
+    pub fn warm_idle(ecu_id: [u8; 2]) -> Self {
+        let mut ecu = Self::new(ecu_id);
+        for (addr, value) in [
+            (ObdAddress::RpmHigh, 0x0f),
+            (ObdAddress::RpmLow, 0xf0),
+            (ObdAddress::TargetIdle, 102),
+            (ObdAddress::EngineCoolantTemperature, 208),
+            (ObdAddress::IntakeAirTemperature, 112),
+            (ObdAddress::TpsVoltage, 26),
+            (ObdAddress::InjectorPulseWidthHigh, 0x04),
+            (ObdAddress::InjectorPulseWidthLow, 0xe2),
+            (ObdAddress::IgnitionAdvance, 57),
+            (ObdAddress::ManifoldAbsolutePressure, 84),
+            (ObdAddress::BarometricPressure, 184),
+            (ObdAddress::IscFlowDuty, 77),
+            (ObdAddress::BatteryVoltage, 178),
+            (ObdAddress::StatusFlags, 0b0001_0000),
+        ] {
+            ecu.set(addr, value);
+        }
+        ecu
+    }
+
+    pub fn set(&mut self, addr: ObdAddress, value: u8) {
+        self.memory[addr as usize] = value;
+    }
+
+    pub fn get(&self, addr: ObdAddress) -> u8 {
+        self.memory[addr as usize]
+    }
+
+    /// Program current and history fault codes into the fault code bytes.
+    pub fn set_fault_codes(&mut self, current: &[FaultCode], history: &[FaultCode]) {
+        for (addrs, codes) in [
+            (CURRENT_FAULT_ADDRESSES, current),
+            (HISTORY_FAULT_ADDRESSES, history),
+        ] {
+            for (addr, byte) in addrs.iter().zip(encode_fault_bytes(codes)) {
+                self.set(*addr, byte);
+            }
+        }
+    }
+
+    /// ECU answer to a request, not including the echo.
+    pub fn respond(&mut self, request: &SdlMessage) -> Option<SdlMessage> {
+        match request.header() {
+            SdlHeader::Id => Some(SdlMessage::new(SdlHeader::Id, Some(self.ecu_id.to_vec()))),
+            SdlHeader::Data => {
+                let values = request
+                    .data()
+                    .iter()
+                    .map(|addr| self.memory[*addr as usize])
+                    .collect();
+                Some(SdlMessage::new(SdlHeader::Data, Some(values)))
+            }
+            SdlHeader::ClearCodes => {
+                for addr in CURRENT_FAULT_ADDRESSES
+                    .iter()
+                    .chain(HISTORY_FAULT_ADDRESSES.iter())
+                {
+                    self.set(*addr, 0);
+                }
+                Some(SdlMessage::new(SdlHeader::ClearCodes, None))
+            }
+            SdlHeader::Actuate => {
+                if let [command, on] = request.data() {
+                    if *on != 0 {
+                        self.active_tests.insert(*command);
+                    } else {
+                        self.active_tests.remove(command);
+                    }
+                }
+                Some(request.clone())
+            }
+        }
+    }
+
+    /// Answer requests on `port` until the other end goes away. Every request is echoed back
+    /// before the response, like the K-line does.
+    pub fn serve(&mut self, port: &mut (impl Read + Write + ?Sized)) -> io::Result<()> {
+        let mut decoder = SdlFrameDecoder::new();
+        let mut chunk = [0u8; 64];
+        loop {
+            match port.read(&mut chunk) {
+                Ok(0) => return Ok(()),
+                Ok(len) => decoder.push(&chunk[..len]),
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::TimedOut
+                            | io::ErrorKind::WouldBlock
+                            | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+            while let Some(request) = decoder.next_frame() {
+                let mut reply = request.to_bytes();
+                if let Some(response) = self.respond(&request) {
+                    reply.extend(response.to_bytes());
+                }
+                port.write_all(&reply)?;
+            }
+        }
+    }
+
+    /// Serve one TCP client after another, forever.
+    pub fn serve_tcp(&mut self, listener: &TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            stream.set_nodelay(true)?;
+            // a client dropping the connection is routine, wait for the next one
+            let _ = self.serve(&mut stream);
+        }
+        Ok(())
+    }
+}
+
+/// Pseudo-terminal pair for serving the emulator. The ECU side is served, the app opens the
+/// device named by the second port as if it was the KKL cable. Keep both open while serving.
+pub fn open_pty() -> io::Result<(TTYPort, TTYPort)> {
+    let (mut ecu_side, app_side) = TTYPort::pair()?;
+    ecu_side.set_timeout(Duration::from_secs(1))?;
+    Ok((ecu_side, app_side))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    use crate::{
+        connection::TcpConnector,
+        dtc::FaultCode,
+        emulator::{EcuEmulator, SIMULATOR_ECU},
+        sdl::{ObdAddress, SdlHeader, SdlMessage, SuzukiSdlViewer},
+    };
+
+    #[test]
+    fn test_respond() {
+        let mut ecu = EcuEmulator::new([0x31, 0x09]);
+        ecu.set(ObdAddress::RpmHigh, 0x12);
+        assert_eq!(
+            ecu.respond(&SdlMessage::new(SdlHeader::Id, None)),
+            Some(SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])))
+        );
+        assert_eq!(
+            ecu.respond(&SdlMessage::new(SdlHeader::Data, Some(vec![0x04, 0xc4]))),
+            Some(SdlMessage::new(SdlHeader::Data, Some(vec![0x12, 0x00])))
+        );
+
+        let on = SdlMessage::new(SdlHeader::Actuate, Some(vec![0x03, 0x01]));
+        assert_eq!(ecu.respond(&on), Some(on));
+        assert!(ecu.active_tests.contains(&0x03));
+
+        ecu.set_fault_codes(&[FaultCode::EctHigh], &[FaultCode::Egr]);
+        assert_eq!(ecu.get(ObdAddress::FaultCodes1), 0b0000_0010);
+        assert_eq!(ecu.get(ObdAddress::FaultCodes6), 0b0000_0010);
+        ecu.respond(&SdlMessage::new(SdlHeader::ClearCodes, None));
+        assert_eq!(ecu.get(ObdAddress::FaultCodes6), 0);
+    }
+
+    #[test]
+    fn test_viewer_against_emulator_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut ecu = EcuEmulator::warm_idle(SIMULATOR_ECU.id);
+            ecu.set_fault_codes(&[], &[FaultCode::IatLow]);
+            ecu.serve_tcp(&listener)
+        });
+
+        let mut viewer = SuzukiSdlViewer::with_connector(TcpConnector {
+            addr: addr.to_string(),
+            timeout: Duration::from_secs(1),
+        });
+        viewer.extra_ecus = &[SIMULATOR_ECU];
+        viewer.poll_ecu().unwrap();
+        assert!(viewer.connection_state.is_streaming());
+        assert_eq!(viewer.ecu.map(|ecu| ecu.model), Some("Simulator"));
+        assert_eq!(viewer.poll_ecu(), Ok(true));
+        viewer.update_processed_data();
+        assert_eq!(viewer.engine_context.engine_speed, 800);
+        assert_eq!(viewer.engine_context.coolant_temp, 90);
+        assert_eq!(
+            viewer.read_fault_codes().unwrap().history,
+            vec![FaultCode::IatLow]
+        );
+        viewer.clear_fault_codes().unwrap();
+        assert!(viewer.fault_codes.is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+use std::{fmt, io};
+
+/// Everything that can go wrong while talking SDL to the ECU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdlError {
+    /// ECU did not answer within the port read timeout.
+    Timeout,
+
+    /// Echo of the request read back from the K-line differs from what was sent.
+    EchoMismatch { sent: Vec<u8>, received: Vec<u8> },
+
+    /// Frame checksum does not match its contents.
+    BadChecksum { expected: u8, actual: u8 },
+
+    /// First byte of a frame is not a known [`crate::sdl::SdlHeader`].
+    BadHeader(u8),
+
+    /// Frame is shorter than its header or length byte claims.
+    TruncatedFrame { expected: usize, actual: usize },
+
+    /// Well-formed frame that does not answer the request, e.g. a negative actuation ack.
+    UnexpectedResponse(Vec<u8>),
+
+    /// Request refused locally before being sent, e.g. actuation while driving.
+    Refused(&'static str),
+
+    /// Port was never opened or has gone away (cable unplugged, socket closed).
+    PortGone(io::ErrorKind),
+}
+
+impl fmt::Display for SdlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdlError::Timeout => write!(f, "timed out waiting for ECU"),
+            SdlError::EchoMismatch { sent, received } => {
+                write!(f, "echo mismatch: sent {sent:02x?}, got {received:02x?}")
+            }
+            SdlError::BadChecksum { expected, actual } => {
+                write!(
+                    f,
+                    "bad checksum: expected {expected:#04x}, got {actual:#04x}"
+                )
+            }
+            SdlError::BadHeader(header) => write!(f, "bad header {header:#04x}"),
+            SdlError::TruncatedFrame { expected, actual } => {
+                write!(
+                    f,
+                    "truncated frame: expected {expected} bytes, got {actual}"
+                )
+            }
+            SdlError::UnexpectedResponse(bytes) => write!(f, "unexpected response {bytes:02x?}"),
+            SdlError::Refused(reason) => write!(f, "refused: {reason}"),
+            SdlError::PortGone(kind) => write!(f, "port gone: {kind}"),
+        }
+    }
+}
+
+impl std::error::Error for SdlError {}
+
+impl From<io::Error> for SdlError {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => SdlError::Timeout,
+            kind => SdlError::PortGone(kind),
+        }
+    }
+}
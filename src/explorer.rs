@@ -0,0 +1,161 @@
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use crate::{error::SdlError, sdl::SuzukiSdlViewer};
+
+/// Addresses swept per request, on top of the pinned ones.
+pub const SWEEP_BLOCK_SIZE: usize = 16;
+
+/// Most addresses that can be pinned, keeps requests well within a single frame.
+pub const MAX_PINNED: usize = 32;
+
+/// A value counts as changed for this long after it last changed.
+pub const CHANGE_HIGHLIGHT: Duration = Duration::from_secs(2);
+
+/// Reads the whole 0x00-0xFF address space to hunt for undocumented parameters. A block of
+/// addresses is swept per poll, pinned addresses are read every poll.
+#[derive(Debug, Clone)]
+pub struct RawExplorer {
+    values: [Option<u8>; 256],
+    changed_at: [Option<Instant>; 256],
+    pinned: BTreeSet<u8>,
+    next_block: usize,
+    /// Address selected on the explorer page.
+    pub cursor: u8,
+}
+
+impl Default for RawExplorer {
+    fn default() -> Self {
+        Self {
+            values: [None; 256],
+            changed_at: [None; 256],
+            pinned: BTreeSet::new(),
+            next_block: 0,
+            cursor: 0,
+        }
+    }
+}
+
+impl RawExplorer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last value read from `addr`, `None` if not read yet.
+    pub fn value(&self, addr: u8) -> Option<u8> {
+        self.values[addr as usize]
+    }
+
+    /// Whether `addr` changed within [`CHANGE_HIGHLIGHT`] of `now`.
+    pub fn recently_changed(&self, addr: u8, now: Instant) -> bool {
+        self.changed_at[addr as usize]
+            .is_some_and(|changed_at| now.duration_since(changed_at) < CHANGE_HIGHLIGHT)
+    }
+
+    pub fn pinned(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pinned.iter().copied()
+    }
+
+    /// Pin or unpin `addr`. Returns whether it is pinned now, pinning is refused once
+    /// [`MAX_PINNED`] addresses are pinned.
+    pub fn toggle_pin(&mut self, addr: u8) -> bool {
+        if self.pinned.remove(&addr) {
+            return false;
+        }
+        if self.pinned.len() >= MAX_PINNED {
+            return false;
+        }
+        self.pinned.insert(addr)
+    }
+
+    /// Addresses for the next request: pinned ones plus the next block of the sweep.
+    pub fn next_addresses(&self) -> Vec<u8> {
+        let start = self.next_block * SWEEP_BLOCK_SIZE;
+        let mut addrs: BTreeSet<u8> = (start..start + SWEEP_BLOCK_SIZE)
+            .map(|addr| addr as u8)
+            .collect();
+        addrs.extend(&self.pinned);
+        addrs.into_iter().collect()
+    }
+
+    /// Store values read for `addrs`, noting which ones changed.
+    pub fn record(&mut self, addrs: &[u8], values: &[u8], now: Instant) {
+        for (addr, value) in addrs.iter().zip(values) {
+            let previous = self.values[*addr as usize].replace(*value);
+            if previous.is_some_and(|previous| previous != *value) {
+                self.changed_at[*addr as usize] = Some(now);
+            }
+        }
+    }
+
+    /// Read the next block and the pinned addresses from the ECU.
+    pub fn poll(&mut self, viewer: &mut SuzukiSdlViewer) -> Result<(), SdlError> {
+        let addrs = self.next_addresses();
+        let values = viewer.query_raw(&addrs)?;
+        self.record(&addrs, &values, Instant::now());
+        self.next_block = (self.next_block + 1) % (256 / SWEEP_BLOCK_SIZE);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        explorer::{MAX_PINNED, RawExplorer, SWEEP_BLOCK_SIZE},
+        sdl::{SdlHeader, SdlMessage, SuzukiSdlViewer},
+        transport::MockTransport,
+    };
+
+    #[test]
+    fn test_sweep_with_pinned() {
+        let mock = MockTransport::new();
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        let mut explorer = RawExplorer::new();
+        assert!(explorer.toggle_pin(0xc4));
+        assert_eq!(explorer.next_addresses().len(), SWEEP_BLOCK_SIZE + 1);
+
+        for block in 0..256 / SWEEP_BLOCK_SIZE {
+            let values = explorer
+                .next_addresses()
+                .iter()
+                .map(|addr| if *addr == 0xc4 { 0x42 } else { block as u8 })
+                .collect();
+            mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(values)).to_bytes());
+            explorer.poll(&mut viewer).unwrap();
+        }
+        assert_eq!(explorer.value(0x00), Some(0));
+        assert_eq!(explorer.value(0xff), Some(15));
+        assert_eq!(explorer.value(0xc4), Some(0x42));
+        // sweep wraps around
+        assert_eq!(explorer.next_addresses()[0], 0x00);
+    }
+
+    #[test]
+    fn test_change_highlighting() {
+        let mut explorer = RawExplorer::new();
+        let start = Instant::now();
+        explorer.record(&[0x30, 0x31], &[1, 1], start);
+        assert!(!explorer.recently_changed(0x30, start));
+
+        let later = start + Duration::from_secs(1);
+        explorer.record(&[0x30, 0x31], &[2, 1], later);
+        assert!(explorer.recently_changed(0x30, later));
+        assert!(!explorer.recently_changed(0x31, later));
+        assert!(!explorer.recently_changed(0x30, later + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_pin_limit() {
+        let mut explorer = RawExplorer::new();
+        for addr in 0..MAX_PINNED as u8 {
+            assert!(explorer.toggle_pin(addr));
+        }
+        assert!(!explorer.toggle_pin(0xff));
+        assert!(!explorer.toggle_pin(0x00));
+        assert_eq!(explorer.pinned().count(), MAX_PINNED - 1);
+    }
+}
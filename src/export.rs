@@ -0,0 +1,263 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::sdl::EngineContext;
+
+/// A column of the export, one per [`EngineContext`] field. Named after the field so a header
+/// reads the same as the code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Channel {
+    DesiredIdle,
+    EngineSpeed,
+    IscFlowDuty,
+    AbsoluteThrottlePosition,
+    ThrottleAngle,
+    InjectorPulseWidthCyl1,
+    CoolantTemp,
+    VehicleSpeed,
+    IntakeAirTemperature,
+    ManifoldAbsolutePressure,
+    BarometricPressure,
+    BatteryVoltage,
+    IgnitionAdvance,
+    ClosedThrottlePosition,
+    ElectricLoad,
+    FuelCut,
+    AcSwitch,
+    PspSwitch,
+    RadiatorFan,
+    CalculatedLoad,
+    InstantConsumption,
+    CumulativeDistance,
+    CumulativeFuel,
+    TotalFuelUsed,
+    FuelConsumption,
+    FuelFlowRate,
+}
+
+impl Channel {
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Channel::DesiredIdle | Channel::EngineSpeed => "rpm",
+            Channel::IscFlowDuty | Channel::AbsoluteThrottlePosition | Channel::CalculatedLoad => {
+                "%"
+            }
+            Channel::ThrottleAngle => "deg",
+            Channel::InjectorPulseWidthCyl1 => "ms",
+            Channel::CoolantTemp | Channel::IntakeAirTemperature => "C",
+            Channel::VehicleSpeed => "km/h",
+            Channel::ManifoldAbsolutePressure | Channel::BarometricPressure => "kPa",
+            Channel::BatteryVoltage => "V",
+            Channel::IgnitionAdvance => "deg BTDC",
+            Channel::ClosedThrottlePosition
+            | Channel::ElectricLoad
+            | Channel::FuelCut
+            | Channel::AcSwitch
+            | Channel::PspSwitch
+            | Channel::RadiatorFan => "on/off",
+            Channel::InstantConsumption | Channel::FuelConsumption => "L/100km",
+            Channel::CumulativeDistance => "km",
+            Channel::CumulativeFuel | Channel::TotalFuelUsed => "L",
+            Channel::FuelFlowRate => "L/h",
+        }
+    }
+
+    /// Processed value in `context`, switches as 1 or 0. The one place values are read out of
+    /// the context for graphs, freeze frames and the export alike.
+    pub fn value(&self, context: &EngineContext) -> f64 {
+        let switch = |on: bool| if on { 1.0 } else { 0.0 };
+        match self {
+            Channel::DesiredIdle => context.desired_idle as f64,
+            Channel::EngineSpeed => context.engine_speed as f64,
+            Channel::IscFlowDuty => context.isc_flow_duty as f64,
+            Channel::AbsoluteThrottlePosition => context.absolute_throttle_position as f64,
+            Channel::ThrottleAngle => context.throttle_angle as f64,
+            Channel::InjectorPulseWidthCyl1 => context.injector_pulse_width_cyl_1 as f64,
+            Channel::CoolantTemp => context.coolant_temp as f64,
+            Channel::VehicleSpeed => context.vehicle_speed as f64,
+            Channel::IntakeAirTemperature => context.intake_air_temperature as f64,
+            Channel::ManifoldAbsolutePressure => context.manifold_absolute_pressure as f64,
+            Channel::BarometricPressure => context.barometric_pressure as f64,
+            Channel::BatteryVoltage => context.battery_voltage as f64,
+            Channel::IgnitionAdvance => context.ignition_advance as f64,
+            Channel::ClosedThrottlePosition => switch(context.closed_throttle_position),
+            Channel::ElectricLoad => switch(context.electric_load),
+            Channel::FuelCut => switch(context.fuel_cut),
+            Channel::AcSwitch => switch(context.ac_switch),
+            Channel::PspSwitch => switch(context.psp_switch),
+            Channel::RadiatorFan => switch(context.radiator_fan),
+            Channel::CalculatedLoad => context.calculated_load as f64,
+            Channel::InstantConsumption => context.instant_consumption,
+            Channel::CumulativeDistance => context.cumulative_distance,
+            Channel::CumulativeFuel => context.cumulative_fuel,
+            Channel::TotalFuelUsed => context.total_fuel_used,
+            Channel::FuelConsumption => context.fuel_consumption,
+            Channel::FuelFlowRate => context.fuel_flow_rate,
+        }
+    }
+
+    /// Value as written to the file. Single precision is plenty for what the ECU resolves and
+    /// keeps widened readings like 13.8 V from printing as 13.800000190734863.
+    pub fn format(&self, context: &EngineContext) -> String {
+        (self.value(context) as f32).to_string()
+    }
+}
+
+/// `time` as ISO 8601 in UTC with milliseconds, e.g. `2024-06-10T06:13:20.123Z`.
+pub fn format_utc(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // days to civil date, from Howard Hinnant's date algorithms
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Writes processed values as CSV, one row per poll. The header names every channel with its
+/// unit, e.g. `engine_speed (rpm)`, after a `time (UTC)` column.
+#[derive(Debug)]
+pub struct CsvExporter<W: Write> {
+    writer: W,
+    channels: Vec<Channel>,
+    /// Rows closer together than this are dropped, to keep long drives manageable.
+    interval: Option<Duration>,
+    last_row: Option<SystemTime>,
+}
+
+impl<W: Write> CsvExporter<W> {
+    /// Export `channels` in that order, or every channel if none are given.
+    pub fn new(writer: W, channels: &[Channel], interval: Option<Duration>) -> Self {
+        let channels = if channels.is_empty() {
+            Channel::iter().collect()
+        } else {
+            channels.to_vec()
+        };
+        Self {
+            writer,
+            channels,
+            interval,
+            last_row: None,
+        }
+    }
+
+    /// Write a row for `context` as polled at `at`, the header first if this is the first one.
+    pub fn write(&mut self, at: SystemTime, context: &EngineContext) -> io::Result<()> {
+        match self.last_row {
+            None => {
+                let mut header = vec!["time (UTC)".to_string()];
+                header.extend(
+                    self.channels
+                        .iter()
+                        .map(|channel| format!("{channel} ({})", channel.unit())),
+                );
+                writeln!(self.writer, "{}", header.join(","))?;
+            }
+            Some(last_row) => {
+                if let Some(interval) = self.interval
+                    && at.duration_since(last_row).unwrap_or_default() < interval
+                {
+                    return Ok(());
+                }
+            }
+        }
+        let mut row = vec![format_utc(at)];
+        row.extend(self.channels.iter().map(|channel| channel.format(context)));
+        writeln!(self.writer, "{}", row.join(","))?;
+        self.last_row = Some(at);
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use crate::{
+        export::{Channel, CsvExporter, format_utc},
+        sdl::EngineContext,
+    };
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_millis(1_718_000_000_123)),
+            "2024-06-10T06:13:20.123Z"
+        );
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn test_channels_and_decimation() {
+        assert_eq!(Channel::from_str("engine_speed"), Ok(Channel::EngineSpeed));
+        assert!(Channel::from_str("rpm").is_err());
+
+        let context = EngineContext {
+            engine_speed: 2500,
+            battery_voltage: 13.8,
+            radiator_fan: true,
+            ..EngineContext::default()
+        };
+        let channels = [
+            Channel::EngineSpeed,
+            Channel::BatteryVoltage,
+            Channel::RadiatorFan,
+        ];
+        let mut exporter = CsvExporter::new(Vec::new(), &channels, Some(Duration::from_secs(1)));
+        let start = UNIX_EPOCH + Duration::from_secs(1_718_000_000);
+        for millis in (0..2500).step_by(100) {
+            exporter
+                .write(start + Duration::from_millis(millis), &context)
+                .unwrap();
+        }
+        let csv = String::from_utf8(exporter.into_inner()).unwrap();
+        assert_eq!(
+            csv,
+            "time (UTC),engine_speed (rpm),battery_voltage (V),radiator_fan (on/off)\n\
+             2024-06-10T06:13:20.000Z,2500,13.8,1\n\
+             2024-06-10T06:13:21.000Z,2500,13.8,1\n\
+             2024-06-10T06:13:22.000Z,2500,13.8,1\n"
+        );
+
+        let mut exporter = CsvExporter::new(Vec::new(), &[], None);
+        exporter.write(start, &context).unwrap();
+        exporter.write(start, &context).unwrap();
+        let csv = String::from_utf8(exporter.into_inner()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].split(',').count(), 27);
+    }
+}
@@ -0,0 +1,408 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+use crate::{
+    alert::{CHARGING_VOLTAGE, LUGGING_LOAD, LUGGING_RPM, OVERHEAT_COOLANT_TEMP},
+    sdl::{EngineContext, ObdAddress},
+};
+
+/// Frames kept, the oldest is dropped to make room for a new one.
+pub const FREEZE_FRAME_CAPACITY: usize = 50;
+
+/// A trigger that fired stays quiet this long, so a value hovering on a threshold doesn't fill
+/// the store with the same event.
+pub const RETRIGGER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What made a frame get captured.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+pub enum Trigger {
+    /// ECT at or over [`OVERHEAT_COOLANT_TEMP`], where the temperature gauge blinks.
+    Overheat,
+    /// ECU supply outside [`CHARGING_VOLTAGE`] with the engine running, where the battery
+    /// reading turns red. An engine that's off is left out, cranking would capture on every start.
+    Battery,
+    /// Load at or over [`LUGGING_LOAD`] under [`LUGGING_RPM`] with the engine running, where the
+    /// load gauge blinks.
+    Lugging,
+    /// Captured by key press.
+    Manual,
+}
+
+impl Trigger {
+    /// Whether `context` shows what this trigger is about, always false for [`Trigger::Manual`].
+    pub fn is_active(&self, context: &EngineContext) -> bool {
+        let running = context.engine_speed > 0;
+        match self {
+            Trigger::Overheat => context.coolant_temp >= OVERHEAT_COOLANT_TEMP,
+            Trigger::Battery => running && !CHARGING_VOLTAGE.contains(&context.battery_voltage),
+            Trigger::Lugging => {
+                running
+                    && context.engine_speed < LUGGING_RPM
+                    && context.calculated_load >= LUGGING_LOAD
+            }
+            Trigger::Manual => false,
+        }
+    }
+}
+
+const AUTOMATIC_TRIGGERS: [Trigger; 3] = [Trigger::Overheat, Trigger::Battery, Trigger::Lugging];
+
+/// Everything known about the engine at one moment, like a scan tool's freeze frame for a DTC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FreezeFrame {
+    pub trigger: Trigger,
+    /// Wall-clock time of the poll captured.
+    pub at: SystemTime,
+    pub ecu_id: Option<[u8; 2]>,
+    pub context: EngineContext,
+    /// Address and raw value pairs, in address order.
+    pub raw_data: Vec<(u8, u8)>,
+}
+
+impl FreezeFrame {
+    pub fn new(
+        trigger: Trigger,
+        at: SystemTime,
+        ecu_id: Option<[u8; 2]>,
+        context: &EngineContext,
+        raw_data: &HashMap<ObdAddress, u8>,
+    ) -> Self {
+        let mut raw_data: Vec<(u8, u8)> = raw_data
+            .iter()
+            .map(|(addr, value)| (*addr as u8, *value))
+            .collect();
+        raw_data.sort();
+        Self {
+            trigger,
+            at,
+            ecu_id,
+            context: *context,
+            raw_data,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FreezeFrameFile {
+    #[serde(default)]
+    frames: VecDeque<FreezeFrame>,
+}
+
+/// Captured frames, oldest first, saved to a TOML file after every capture so they can be looked
+/// at after the drive.
+#[derive(Debug, Default)]
+pub struct FreezeFrameStore {
+    frames: VecDeque<FreezeFrame>,
+    path: Option<PathBuf>,
+    /// Triggers active at the last update, a frame is captured when one turns active.
+    active: Vec<Trigger>,
+    /// Frame shown on the freeze frames page, counted from the newest.
+    pub selected: usize,
+}
+
+impl FreezeFrameStore {
+    /// A store kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the frames saved at `path`, an empty store if there is no file yet. The store always
+    /// saves to `path`, whatever went wrong loading it is returned along: a file that can't be
+    /// parsed is moved aside to `<path>.bad` so new captures don't overwrite it.
+    pub fn open(path: impl AsRef<Path>) -> (Self, Option<io::Error>) {
+        let path = path.as_ref();
+        let (frames, error) = match fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str::<FreezeFrameFile>(&contents) {
+                Ok(file) => (file.frames, None),
+                Err(err) => {
+                    let mut bad = path.as_os_str().to_owned();
+                    bad.push(".bad");
+                    let error = match fs::rename(path, &bad) {
+                        Ok(()) => io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{} unreadable, moved aside: {err}", path.display()),
+                        ),
+                        Err(rename_err) => rename_err,
+                    };
+                    (VecDeque::new(), Some(error))
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (VecDeque::new(), None),
+            Err(err) => (VecDeque::new(), Some(err)),
+        };
+        let store = Self {
+            frames,
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        };
+        (store, error)
+    }
+
+    /// Frames, newest first.
+    pub fn frames(&self) -> impl DoubleEndedIterator<Item = &FreezeFrame> {
+        self.frames.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn selected_frame(&self) -> Option<&FreezeFrame> {
+        self.frames().nth(self.selected)
+    }
+
+    pub fn select_newer(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_older(&mut self) {
+        self.selected = (self.selected + 1).min(self.len().saturating_sub(1));
+    }
+
+    /// Capture a frame for every automatic trigger that turned active since the last poll,
+    /// unless it fired within [`RETRIGGER_INTERVAL`].
+    pub fn update(
+        &mut self,
+        at: SystemTime,
+        ecu_id: Option<[u8; 2]>,
+        context: &EngineContext,
+        raw_data: &HashMap<ObdAddress, u8>,
+    ) -> io::Result<()> {
+        let active: Vec<Trigger> = AUTOMATIC_TRIGGERS
+            .into_iter()
+            .filter(|trigger| trigger.is_active(context))
+            .collect();
+        let fired: Vec<Trigger> = active
+            .iter()
+            .filter(|trigger| !self.active.contains(trigger))
+            .filter(|trigger| !self.fired_within(**trigger, at, RETRIGGER_INTERVAL))
+            .copied()
+            .collect();
+        self.active = active;
+        for trigger in fired {
+            self.capture(FreezeFrame::new(trigger, at, ecu_id, context, raw_data))?;
+        }
+        Ok(())
+    }
+
+    fn fired_within(&self, trigger: Trigger, at: SystemTime, interval: Duration) -> bool {
+        self.frames()
+            .find(|frame| frame.trigger == trigger)
+            .is_some_and(|frame| at.duration_since(frame.at).unwrap_or_default() < interval)
+    }
+
+    /// Keep `frame`, dropping the oldest if full, and save. The frame is kept even if saving
+    /// fails.
+    pub fn capture(&mut self, frame: FreezeFrame) -> io::Result<()> {
+        if self.frames.len() == FREEZE_FRAME_CAPACITY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+        // stay on the frame being looked at
+        if self.selected > 0 {
+            self.selected = (self.selected + 1).min(self.len() - 1);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = FreezeFrameFile {
+            frames: self.frames.clone(),
+        };
+        let contents = toml::to_string(&file)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let tmp_file = path.with_extension("tmp");
+        fs::write(&tmp_file, contents)?;
+        fs::rename(tmp_file, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{
+        freeze_frame::{FREEZE_FRAME_CAPACITY, FreezeFrame, FreezeFrameStore, Trigger},
+        sdl::{EngineContext, ObdAddress},
+    };
+
+    fn running() -> EngineContext {
+        EngineContext {
+            engine_speed: 2000,
+            coolant_temp: 90,
+            battery_voltage: 14.0,
+            calculated_load: 40,
+            ..EngineContext::default()
+        }
+    }
+
+    #[test]
+    fn test_triggers_fire_once_per_event() {
+        let mut store = FreezeFrameStore::new();
+        let raw_data = HashMap::from([(ObdAddress::RpmHigh, 0x40)]);
+        let start = SystemTime::now();
+        let overheating = EngineContext {
+            coolant_temp: 112,
+            ..running()
+        };
+        let steps = [
+            (0, running()),
+            (1, overheating),
+            (2, overheating),
+            // hovering on the threshold
+            (3, running()),
+            (4, overheating),
+            (5, running()),
+            (70, overheating),
+            (
+                71,
+                EngineContext {
+                    battery_voltage: 12.0,
+                    calculated_load: 90,
+                    ..overheating
+                },
+            ),
+            // off with the key on, the load and voltage look bad but nothing is wrong
+            (
+                80,
+                EngineContext {
+                    engine_speed: 0,
+                    battery_voltage: 12.5,
+                    calculated_load: 100,
+                    ..running()
+                },
+            ),
+        ];
+        for (secs, context) in steps {
+            let at = start + Duration::from_secs(secs);
+            store
+                .update(at, Some([0x12, 0x34]), &context, &raw_data)
+                .unwrap();
+        }
+        let fired: Vec<(Trigger, u64)> = store
+            .frames()
+            .map(|frame| {
+                let secs = frame.at.duration_since(start).unwrap().as_secs();
+                (frame.trigger, secs)
+            })
+            .collect();
+        assert_eq!(
+            fired,
+            vec![
+                (Trigger::Lugging, 71),
+                (Trigger::Battery, 71),
+                (Trigger::Overheat, 70),
+                (Trigger::Overheat, 1),
+            ]
+        );
+        let frame = store.selected_frame().unwrap();
+        assert_eq!(frame.context.calculated_load, 90);
+        assert_eq!(frame.raw_data, vec![(ObdAddress::RpmHigh as u8, 0x40)]);
+    }
+
+    #[test]
+    fn test_saved_and_loaded() {
+        let path =
+            std::env::temp_dir().join(format!("suzui-freeze-frames-{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (mut store, error) = FreezeFrameStore::open(&path);
+        assert!(store.is_empty());
+        assert!(error.is_none());
+
+        let start = SystemTime::now();
+        let context = EngineContext {
+            injector_pulse_width_cyl_1: 2.5,
+            instant_consumption: f64::INFINITY,
+            radiator_fan: true,
+            ..running()
+        };
+        let raw_data = HashMap::from([(ObdAddress::EngineCoolantTemperature, 0x5a)]);
+        for i in 0..FREEZE_FRAME_CAPACITY + 2 {
+            let frame = FreezeFrame::new(
+                Trigger::Manual,
+                start + Duration::from_secs(i as u64),
+                None,
+                &context,
+                &raw_data,
+            );
+            store.capture(frame).unwrap();
+        }
+        store.select_older();
+        store.select_older();
+
+        let (loaded, _) = FreezeFrameStore::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), FREEZE_FRAME_CAPACITY);
+        assert_eq!(
+            loaded.frames().collect::<Vec<_>>(),
+            store.frames().collect::<Vec<_>>()
+        );
+        let oldest = loaded.frames().next_back().unwrap();
+        assert_eq!(oldest.at, start + Duration::from_secs(2));
+        assert_eq!(oldest.context, context);
+
+        // a new frame doesn't move the selection off the one being looked at
+        let selected = store.selected_frame().cloned();
+        store
+            .capture(FreezeFrame::new(
+                Trigger::Manual,
+                start,
+                None,
+                &context,
+                &raw_data,
+            ))
+            .unwrap();
+        assert_eq!(store.selected_frame().cloned(), selected);
+    }
+
+    #[test]
+    fn test_corrupt_file_moved_aside() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("suzui-freeze-corrupt-{}.toml", std::process::id()));
+        let bad = dir.join(format!(
+            "suzui-freeze-corrupt-{}.toml.bad",
+            std::process::id()
+        ));
+        std::fs::write(&path, "[[frames]]\ntrigger = 12").unwrap();
+
+        let (mut store, error) = FreezeFrameStore::open(&path);
+        assert!(error.is_some());
+        assert!(store.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(&bad).unwrap(),
+            "[[frames]]\ntrigger = 12"
+        );
+
+        // saving carries on at the same path
+        let frame = FreezeFrame::new(
+            Trigger::Manual,
+            SystemTime::now(),
+            None,
+            &running(),
+            &HashMap::new(),
+        );
+        store.capture(frame).unwrap();
+        let (loaded, error) = FreezeFrameStore::open(&path);
+        assert!(error.is_none());
+        assert_eq!(loaded.len(), 1);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(bad).unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use strum::IntoEnumIterator;
+
+use crate::sdl::{EngineContext, ScanToolParameter};
+
+/// Spans the graphs page can show, `w` steps through them.
+pub const GRAPH_WINDOWS: [Duration; 2] = [Duration::from_secs(60), Duration::from_secs(300)];
+
+/// Rolling history of processed values for the graphs page, along with which channels it shows.
+/// Samples are timed by [`EngineContext::last_poll`], so a replay graphs recorded time. A sample
+/// older than the newest one, as when a replay seeks back, clears the history.
+#[derive(Debug, Clone)]
+pub struct History {
+    samples: VecDeque<(Instant, EngineContext)>,
+    /// Channel drawn first, the y axis is labelled with its range.
+    pub primary: ScanToolParameter,
+    /// Channel drawn on top of it, scaled to its own range.
+    pub overlay: Option<ScanToolParameter>,
+    window: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            primary: ScanToolParameter::EngineSpeed,
+            overlay: None,
+            window: 0,
+        }
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `context` if it holds a poll not seen yet.
+    pub fn update(&mut self, context: &EngineContext) {
+        let Some(at) = context.last_poll else {
+            return;
+        };
+        match self.latest() {
+            Some(latest) if at == latest => return,
+            Some(latest) if at < latest => self.samples.clear(),
+            _ => {}
+        }
+        self.samples.push_back((at, *context));
+        let longest = GRAPH_WINDOWS[GRAPH_WINDOWS.len() - 1];
+        while let Some((oldest, _)) = self.samples.front()
+            && at.duration_since(*oldest) > longest
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Time of the newest sample, the right edge of the graphs.
+    pub fn latest(&self) -> Option<Instant> {
+        self.samples.back().map(|(at, _)| *at)
+    }
+
+    pub fn window(&self) -> Duration {
+        GRAPH_WINDOWS[self.window]
+    }
+
+    pub fn next_window(&mut self) {
+        self.window = (self.window + 1) % GRAPH_WINDOWS.len();
+    }
+
+    /// `(seconds before the newest sample, value)` of `parameter` within the window, oldest
+    /// first. Seconds are negative so they plot left of zero.
+    pub fn series(&self, parameter: ScanToolParameter) -> Vec<(f64, f64)> {
+        let Some(latest) = self.latest() else {
+            return Vec::new();
+        };
+        let window = self.window();
+        self.samples
+            .iter()
+            .filter(|(at, _)| latest.duration_since(*at) <= window)
+            .map(|(at, context)| {
+                (
+                    -latest.duration_since(*at).as_secs_f64(),
+                    parameter.value(context),
+                )
+            })
+            .collect()
+    }
+
+    pub fn next_primary(&mut self) {
+        self.primary = step(self.primary, 1);
+        if self.overlay == Some(self.primary) {
+            self.overlay = None;
+        }
+    }
+
+    pub fn previous_primary(&mut self) {
+        self.primary = step(self.primary, ScanToolParameter::iter().len() - 1);
+        if self.overlay == Some(self.primary) {
+            self.overlay = None;
+        }
+    }
+
+    /// Next overlay channel, skipping the primary one, with no overlay after the last.
+    pub fn next_overlay(&mut self) {
+        let next = match self.overlay {
+            None => ScanToolParameter::iter().next(),
+            Some(overlay) => ScanToolParameter::iter()
+                .skip_while(|parameter| *parameter != overlay)
+                .nth(1),
+        };
+        self.overlay = match next {
+            Some(next) if next == self.primary => ScanToolParameter::iter()
+                .skip_while(|parameter| *parameter != next)
+                .nth(1),
+            next => next,
+        };
+    }
+}
+
+/// Parameter `by` places after `parameter`, wrapping around.
+fn step(parameter: ScanToolParameter, by: usize) -> ScanToolParameter {
+    let count = ScanToolParameter::iter().len();
+    let index = ScanToolParameter::iter()
+        .position(|other| other == parameter)
+        .unwrap_or_default();
+    ScanToolParameter::iter()
+        .nth((index + by) % count)
+        .unwrap_or(parameter)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        history::History,
+        sdl::{EngineContext, ScanToolParameter},
+    };
+
+    fn context(at: Instant, rpm: u16) -> EngineContext {
+        EngineContext {
+            engine_speed: rpm,
+            last_poll: Some(at),
+            ..EngineContext::default()
+        }
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let start = Instant::now();
+        let mut history = History::new();
+        history.update(&EngineContext::default());
+        assert_eq!(history.latest(), None);
+
+        for i in 0..4000 {
+            let at = start + Duration::from_millis(100 * i);
+            history.update(&context(at, i as u16));
+            // the same poll drawn twice is kept once
+            history.update(&context(at, i as u16));
+        }
+        let series = history.series(ScanToolParameter::EngineSpeed);
+        assert_eq!(series.len(), 601);
+        assert_eq!(series[0], (-60.0, 3399.0));
+        assert_eq!(series[600], (0.0, 3999.0));
+
+        history.next_window();
+        assert_eq!(history.window(), Duration::from_secs(300));
+        assert_eq!(history.series(ScanToolParameter::EngineSpeed).len(), 3001);
+        assert_eq!(history.samples.len(), 3001);
+
+        // a replay seeking back
+        history.update(&context(start, 800));
+        assert_eq!(
+            history.series(ScanToolParameter::EngineSpeed),
+            vec![(0.0, 800.0)]
+        );
+    }
+
+    #[test]
+    fn test_channel_selection() {
+        let mut history = History::new();
+        history.previous_primary();
+        assert_eq!(history.primary, ScanToolParameter::DesiredIdle);
+        history.previous_primary();
+        assert_eq!(
+            history.primary,
+            ScanToolParameter::iter().next_back().unwrap()
+        );
+        history.next_primary();
+        history.next_primary();
+        assert_eq!(history.primary, ScanToolParameter::EngineSpeed);
+
+        // every other channel once, then none
+        let mut overlays = Vec::new();
+        loop {
+            history.next_overlay();
+            match history.overlay {
+                Some(overlay) => overlays.push(overlay),
+                None => break,
+            }
+        }
+        assert_eq!(overlays.len(), ScanToolParameter::iter().len() - 1);
+        assert!(!overlays.contains(&ScanToolParameter::EngineSpeed));
+
+        history.overlay = Some(ScanToolParameter::IacFlowDutyCycle);
+        history.next_primary();
+        assert_eq!(history.overlay, None);
+    }
+}
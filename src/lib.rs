@@ -1,4 +1,28 @@
+pub mod actuator;
+pub mod alert;
+pub mod config;
+pub mod connection;
+pub mod decoder;
+pub mod discovery;
+pub mod dtc;
+pub mod ecu;
+pub mod emulator;
+pub mod error;
+pub mod explorer;
+pub mod export;
+pub mod freeze_frame;
+pub mod history;
+pub mod page;
+pub mod polling;
+pub mod recorder;
+pub mod replay;
+pub mod scenario;
 pub mod sdl;
+pub mod simulator;
+pub mod sniffer;
+pub mod stats;
 pub mod strings;
 pub mod toggle_detector;
+pub mod transport;
+pub mod vehicle;
 pub mod widgets;
@@ -1,5 +1,12 @@
-use clap::Parser;
-use std::time::{Duration, Instant};
+use clap::{Parser, Subcommand};
+use std::{
+    fs::File,
+    io::{self, BufWriter, LineWriter, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
 
 use color_eyre::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, poll};
@@ -7,18 +14,48 @@ use ratatui::{
     DefaultTerminal, Frame,
     layout::{Constraint, Direction, Layout},
 };
+use serialport::SerialPort;
+use strum::IntoEnumIterator;
 use suzui_rs::{
+    actuator::ActuatorTest,
+    config::{AUTO_PORT, Config, TCP_PORT_PREFIX},
+    connection::Connector,
+    discovery::probe_usb_ports,
+    dtc::{ClearCodesCountdown, FaultCode},
+    ecu::format_ecu_id,
+    emulator::{EcuEmulator, SIMULATOR_ECU, open_pty},
+    error::SdlError,
+    explorer::RawExplorer,
+    export::{Channel, CsvExporter},
+    freeze_frame::{FreezeFrame, FreezeFrameStore, Trigger},
+    history::History,
+    page::Page,
+    recorder::{LogRecord, Recorder},
+    replay::{Replay, SEEK_STEP},
+    scenario::Scenario,
     sdl::SuzukiSdlViewer,
-    strings::DISTANCE_FUEL_FILE_PATH,
-    toggle_detector::ToggleDetector,
+    simulator::EngineSimulator,
+    sniffer::Sniffer,
+    strings::{CONFIG_FILE_PATH, DISTANCE_FUEL_FILE_PATH, FREEZE_FRAME_FILE_PATH, RECORD_DIR_PATH},
+    toggle_detector::{ElGesture, ElGestures},
+    transport::SimulatedTransport,
+    vehicle::VehicleProfile,
     widgets::{
+        actuator::ActuatorPage,
         airflow::{AirflowBlock, AirflowBlockState},
+        diagnostics::DiagnosticsPage,
+        dtc::{ClearCodesPopup, DtcBlock, DtcPage},
         electrical::ElectricalBlock,
         engine::EngineSpeedBlock,
+        explorer::RawExplorerPage,
         flags::FlagsBlock,
+        freeze_frame::FreezeFramePage,
         fuel_ignition::FuelIgnitionBlock,
+        graphs::GraphsPage,
+        status::{NoEcuOverlay, ReplayBar, StatusBar, UnknownEcuPopup},
         temperature::{TemperatureBlock, TemperatureState},
         throttle::ThrottleBlock,
+        trip::TripPage,
         vehicle::VehicleBlock,
     },
 };
@@ -26,19 +63,280 @@ use suzui_rs::{
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(short, long, default_value_t = false)]
     simulate: bool,
+
+    /// Drive the simulated engine from a scenario file instead of the keyboard alone
+    #[arg(long, requires = "simulate")]
+    scenario: Option<PathBuf>,
+
+    /// Config file, options given on the command line take precedence over it
+    #[arg(short, long, default_value = CONFIG_FILE_PATH)]
+    config: PathBuf,
+
+    /// Serial device of the KKL cable, `auto` to probe every USB serial port for the ECU
+    #[arg(short, long)]
+    port: Option<String>,
+
+    #[arg(short, long)]
+    baud_rate: Option<u32>,
+
+    /// How long to wait for the ECU to answer, in milliseconds
+    #[arg(long)]
+    read_timeout_ms: Option<u64>,
+
+    /// Minimum gap between requests, in milliseconds
+    #[arg(long)]
+    request_delay_ms: Option<u64>,
+
+    /// Vehicle profile with injector flow, cylinders, redline etc.
+    #[arg(long)]
+    vehicle_profile: Option<PathBuf>,
+
+    /// Log every poll's raw data to files in this directory
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = RECORD_DIR_PATH)]
+    record: Option<PathBuf>,
+
+    /// Write the processed values of every poll to a CSV file while driving
+    #[arg(long, value_name = "FILE")]
+    export: Option<PathBuf>,
+
+    /// Channels to export, comma separated, e.g. `engine_speed,vehicle_speed`. All of them if
+    /// not given
+    #[arg(long, global = true, value_delimiter = ',')]
+    channel: Vec<Channel>,
+
+    /// Export at most one row per this many milliseconds
+    #[arg(long, global = true)]
+    export_interval_ms: Option<u64>,
+
+    /// Probe every USB serial port for the ECU, print what was found and exit
+    #[arg(long, default_value_t = false)]
+    list_ports: bool,
+
+    /// Listen to another scan tool talking to the ECU and print every exchange, never transmits
+    #[arg(long, default_value_t = false)]
+    sniff: bool,
+
+    /// Run a fake ECU instead of the dashboard, on a new pseudo-terminal (`pty`) or listening on
+    /// a TCP address such as `127.0.0.1:3500`
+    #[arg(long, value_name = "pty|ADDR")]
+    emulate: Option<String>,
+
+    /// ID the emulated ECU answers with, in hex
+    #[arg(long, default_value = "0000", value_parser = parse_ecu_id)]
+    ecu_id: [u8; 2],
+
+    /// Flash code the emulated ECU reports as a current fault, can be given more than once
+    #[arg(long, value_parser = parse_fault_code)]
+    fault_code: Vec<FaultCode>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play a raw data log recorded with --record back on the dashboard
+    Replay {
+        /// Any file of the session, the other parts are played too
+        file: PathBuf,
+    },
+    /// Convert a raw data log recorded with --record to CSV, see --channel and
+    /// --export-interval-ms
+    Export {
+        /// Any file of the session, the other parts are exported too
+        file: PathBuf,
+
+        /// CSV file to write, standard output if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn parse_ecu_id(value: &str) -> Result<[u8; 2], String> {
+    u16::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map(u16::to_be_bytes)
+        .map_err(|err| format!("not a 16-bit hex ID: {err}"))
+}
+
+fn parse_fault_code(value: &str) -> Result<FaultCode, String> {
+    let code: u8 = value.parse().map_err(|err| format!("{err}"))?;
+    FaultCode::iter()
+        .find(|fault| fault.code() == code)
+        .ok_or_else(|| format!("no fault code {code}"))
+}
+
+impl Args {
+    /// Config file overridden by whatever was given on the command line.
+    fn config(&self) -> color_eyre::Result<Config> {
+        let mut config = Config::load_or_default(&self.config)?;
+        if let Some(port) = &self.port {
+            config.port = port.clone();
+        }
+        if let Some(baud_rate) = self.baud_rate {
+            config.baud_rate = baud_rate;
+        }
+        if let Some(read_timeout_ms) = self.read_timeout_ms {
+            config.read_timeout_ms = read_timeout_ms;
+        }
+        if let Some(request_delay_ms) = self.request_delay_ms {
+            config.request_delay_ms = request_delay_ms;
+        }
+        if let Some(vehicle_profile) = &self.vehicle_profile {
+            config.vehicle_profile = Some(vehicle_profile.clone());
+        }
+        if let Some(record) = &self.record {
+            config.record_dir = Some(record.clone());
+        }
+        Ok(config)
+    }
+
+    fn exporter<W: Write>(&self, writer: W) -> CsvExporter<W> {
+        let interval = self.export_interval_ms.map(Duration::from_millis);
+        CsvExporter::new(writer, &self.channel, interval)
+    }
 }
 
 fn main() -> color_eyre::Result<()> {
     let args = Args::parse();
     color_eyre::install()?;
+    let config = args.config()?;
+    if args.list_ports {
+        return list_ports(&config);
+    }
+    if args.sniff {
+        return sniff(&config);
+    }
+    if let Some(target) = &args.emulate {
+        // a current fault is in the history too, like on the real ECU
+        let mut ecu = EcuEmulator::warm_idle(args.ecu_id);
+        ecu.set_fault_codes(&args.fault_code, &args.fault_code);
+        return emulate(ecu, target);
+    }
+    if let Some(Command::Export { file, output }) = &args.command {
+        return export(&args, &config, file, output.as_ref());
+    }
+    let mut simulator = None;
+    let mut replay = None;
+    let mut sdl_viewer = if let Some(Command::Replay { file }) = &args.command {
+        replay = Some(Replay::load(file)?);
+        SuzukiSdlViewer::new(None)
+    } else if args.simulate {
+        let transport = match &args.scenario {
+            Some(path) => SimulatedTransport::with_scenario(Scenario::load(path)?),
+            None => SimulatedTransport::default(),
+        };
+        simulator = Some(transport.engine());
+        let mut viewer = SuzukiSdlViewer::with_transport(transport);
+        viewer.extra_ecus = &[SIMULATOR_ECU];
+        viewer
+    } else {
+        SuzukiSdlViewer::new(Some(config.connector()))
+    };
+    sdl_viewer.request_delay = config.request_delay();
+    sdl_viewer.load_trip(DISTANCE_FUEL_FILE_PATH);
+    if let Some(path) = &config.vehicle_profile {
+        sdl_viewer.vehicle = VehicleProfile::load(path)?;
+    }
+    let mut app = App::new(sdl_viewer, simulator);
+    if let Some(dir) = &config.record_dir
+        && replay.is_none()
+    {
+        app.recorder = Some(Recorder::new(
+            dir,
+            config.record_max_file_size,
+            config.record_max_files,
+        )?);
+    }
+    if let Some(path) = &args.export {
+        app.exporter = Some(args.exporter(LineWriter::new(File::create(path)?)));
+    }
+    (app.freeze_frames, app.freeze_frame_error) = FreezeFrameStore::open(FREEZE_FRAME_FILE_PATH);
+    app.replay = replay;
     let terminal = ratatui::init();
-    let result = App::default().run(terminal, args.simulate);
+    let result = app.run(terminal);
     ratatui::restore();
+    // never leave an active test running behind, even if the main loop failed
+    if let Err(err) = app.stop_actuator_test_on_exit() {
+        eprintln!("Active test may still be running, stopping it failed: {err}");
+    }
     result
 }
 
+fn list_ports(config: &Config) -> color_eyre::Result<()> {
+    let ports = probe_usb_ports(config.baud_rate, config.read_timeout())?;
+    if ports.is_empty() {
+        println!("No USB serial ports found");
+    }
+    for port in ports {
+        let result = match port.ecu_id {
+            Ok(ecu_id) => format!("ECU {}", format_ecu_id(ecu_id)),
+            Err(err) => format!("no ECU ({err})"),
+        };
+        println!("{}\t{}\t{result}", port.path, port.description);
+    }
+    Ok(())
+}
+
+fn sniff(config: &Config) -> color_eyre::Result<()> {
+    // discovery would send ID requests, which is exactly what sniffing must not do
+    if config.port == AUTO_PORT {
+        return Err(color_eyre::eyre::eyre!("--sniff needs an explicit --port"));
+    }
+    let mut port = config.serial_connector().open()?;
+    println!("Listening on {}, Ctrl-C to stop", config.port);
+    let mut sniffer = Sniffer::new(Instant::now());
+    sniffer.run(&mut port, |event| println!("{event}"))?;
+    Ok(())
+}
+
+fn emulate(mut ecu: EcuEmulator, target: &str) -> color_eyre::Result<()> {
+    let id = format_ecu_id(ecu.ecu_id);
+    if target == "pty" {
+        let (mut ecu_side, app_side) = open_pty()?;
+        let path = app_side.name().unwrap_or_default();
+        println!("Emulating ECU {id}, Ctrl-C to stop. Run the dashboard with --port {path}");
+        ecu.serve(&mut ecu_side)?;
+    } else {
+        let listener = TcpListener::bind(target)?;
+        println!(
+            "Emulating ECU {id}, Ctrl-C to stop. Run the dashboard with --port {TCP_PORT_PREFIX}{target}"
+        );
+        ecu.serve_tcp(&listener)?;
+    }
+    Ok(())
+}
+
+fn export(
+    args: &Args,
+    config: &Config,
+    file: &PathBuf,
+    output: Option<&PathBuf>,
+) -> color_eyre::Result<()> {
+    let mut replay = Replay::load(file)?;
+    let mut viewer = SuzukiSdlViewer::new(None);
+    if let Some(path) = &config.vehicle_profile {
+        viewer.vehicle = VehicleProfile::load(path)?;
+    }
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut exporter = args.exporter(BufWriter::new(writer));
+    while !replay.is_finished() {
+        replay.step_forward(&mut viewer);
+        if let Some(at) = replay.recorded_at() {
+            exporter.write(at, &viewer.engine_context)?;
+        }
+    }
+    exporter.into_inner().flush()?;
+    Ok(())
+}
+
+/// Tries at stopping an active test on exit before giving up.
+const EXIT_STOP_ATTEMPTS: usize = 3;
+
 /// The main application which holds the state and logic of the application.
 #[derive(Debug)]
 pub struct App {
@@ -46,27 +344,63 @@ pub struct App {
     running: bool,
     sdl_viewer: SuzukiSdlViewer,
     last_write: Instant,
-    trip_reset_detector: ToggleDetector,
+    last_dtc_read: Instant,
     airflow_state: AirflowBlockState,
     temperature_state: TemperatureState,
-}
-
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
-    }
+    clear_codes_countdown: Option<ClearCodesCountdown>,
+    page: Page,
+    /// Quick EL on-off moving to the next page, hands on the wheel, or six resetting the trip.
+    el_gestures: ElGestures,
+    raw_explorer: RawExplorer,
+    history: History,
+    /// ECU answered with an ID that isn't in the database and the warning wasn't dismissed yet.
+    unknown_ecu_warning: bool,
+    actuator_message: Option<SdlError>,
+    /// Last error from the SDL layer, cleared on the next successful poll.
+    sdl_error: Option<SdlError>,
+    /// Engine model behind `--simulate`, driven from the keyboard.
+    simulator: Option<Arc<Mutex<EngineSimulator>>>,
+    /// Raw data log, when recording.
+    recorder: Option<Recorder>,
+    recorder_error: Option<io::Error>,
+    /// CSV export of every poll, when exporting.
+    exporter: Option<CsvExporter<LineWriter<File>>>,
+    export_error: Option<io::Error>,
+    /// Recorded session played back instead of polling the ECU.
+    replay: Option<Replay>,
+    freeze_frames: FreezeFrameStore,
+    freeze_frame_error: Option<io::Error>,
 }
 
 impl App {
     /// Construct a new instance of [`App`].
-    pub fn new() -> Self {
+    pub fn new(
+        sdl_viewer: SuzukiSdlViewer,
+        simulator: Option<Arc<Mutex<EngineSimulator>>>,
+    ) -> Self {
         Self {
             running: false,
-            sdl_viewer: SuzukiSdlViewer::default(),
+            sdl_viewer,
             last_write: Instant::now(),
-            trip_reset_detector: ToggleDetector::default(),
+            last_dtc_read: Instant::now(),
             airflow_state: AirflowBlockState::new(),
             temperature_state: TemperatureState::new(),
+            clear_codes_countdown: None,
+            page: Page::default(),
+            el_gestures: ElGestures::default(),
+            raw_explorer: RawExplorer::new(),
+            history: History::new(),
+            unknown_ecu_warning: false,
+            actuator_message: None,
+            sdl_error: None,
+            simulator,
+            recorder: None,
+            recorder_error: None,
+            exporter: None,
+            export_error: None,
+            replay: None,
+            freeze_frames: FreezeFrameStore::new(),
+            freeze_frame_error: None,
         }
     }
 
@@ -84,40 +418,145 @@ impl App {
     }
 
     fn reset_trip_meter(&mut self) {
-        self.sdl_viewer.engine_context.cumulative_distance = 0.0;
-        self.sdl_viewer.engine_context.cumulative_fuel = 0.0;
-        self.sdl_viewer.engine_context.total_fuel_used = 0.0;
+        self.sdl_viewer.engine_context.reset_trip();
         self.persistence_write().unwrap();
     }
 
+    /// Clear ECU fault code memory and dismiss the boot countdown.
+    fn clear_fault_codes(&mut self) {
+        self.clear_codes_countdown = None;
+        let result = self.sdl_viewer.clear_fault_codes();
+        self.report(result);
+    }
+
+    /// Keep track of the last SDL error so it can be shown. Timeouts, bad frames etc. only cost
+    /// a poll, the last known values stay on screen and the next poll is tried as normal.
+    fn report<T>(&mut self, result: Result<T, SdlError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.sdl_error = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Start `test`, or stop it if it is the one already running.
+    fn toggle_actuator_test(&mut self, test: ActuatorTest) {
+        let result = if self.sdl_viewer.active_actuator_test == Some(test) {
+            self.sdl_viewer.stop_actuator_test()
+        } else {
+            self.sdl_viewer.start_actuator_test(test)
+        };
+        self.actuator_message = result.err();
+    }
+
+    /// Read fault codes once the ECU answers and offer to clear history codes. Runs again after
+    /// every reconnect since the ECU was most likely power cycled.
+    fn on_ecu_connected(&mut self) {
+        self.sdl_error = None;
+        self.unknown_ecu_warning = self.sdl_viewer.ecu.is_none();
+        let history_codes = self
+            .sdl_viewer
+            .read_fault_codes()
+            .map(|dtcs| dtcs.history.clone());
+        if let Some(history_codes) = self.report(history_codes)
+            && !history_codes.is_empty()
+        {
+            self.clear_codes_countdown = Some(ClearCodesCountdown::new(
+                history_codes,
+                Duration::from_secs(30),
+            ));
+        }
+        self.last_dtc_read = Instant::now();
+    }
+
     /// Run the application's main loop.
-    pub fn run(mut self, mut terminal: DefaultTerminal, should_simulate: bool) -> Result<()> {
+    pub fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
         self.running = true;
-        if !should_simulate {
-            self.sdl_viewer.connect();
-        }
         while self.running {
-            self.sdl_viewer.update_raw_data(should_simulate);
+            match &mut self.replay {
+                Some(replay) => replay.tick(Instant::now(), &mut self.sdl_viewer),
+                None => self.poll()?,
+            }
+            self.history.update(&self.sdl_viewer.engine_context);
+            terminal.draw(|frame| self.render(frame))?;
+            self.handle_crossterm_events()?;
+        }
+        Ok(())
+    }
 
+    /// Stop the active test before exiting, a few tries as the ECU may miss one.
+    fn stop_actuator_test_on_exit(&mut self) -> Result<(), SdlError> {
+        let mut result = Ok(());
+        for _ in 0..EXIT_STOP_ATTEMPTS {
+            result = self.sdl_viewer.stop_actuator_test();
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// One round of talking to the ECU: data, fault codes, actuator safety, then the trip meter.
+    fn poll(&mut self) -> Result<()> {
+        let was_streaming = self.sdl_viewer.connection_state.is_streaming();
+        let polled = self.sdl_viewer.poll_ecu();
+        if self.report(polled) == Some(true) {
+            self.sdl_error = None;
+            // nothing is integrated over the gap before the first poll after a (re)connect
+            let resumed = self.sdl_viewer.engine_context.last_poll.is_none();
             self.sdl_viewer.update_processed_data();
+            self.record_poll(resumed);
+            self.export_poll();
+            self.capture_alerts();
+        }
+        if !was_streaming && self.sdl_viewer.connection_state.is_streaming() {
+            self.on_ecu_connected();
+        }
 
-            // Trip meter reset logic
-            if self
-                .trip_reset_detector
-                .update(self.sdl_viewer.engine_context.electric_load)
-            {
-                self.reset_trip_meter();
-                self.last_write = Instant::now();
-            }
+        if self
+            .clear_codes_countdown
+            .as_ref()
+            .is_some_and(|countdown| countdown.is_elapsed())
+        {
+            self.clear_fault_codes();
+        }
 
-            // Write to file
-            if self.last_write.elapsed() > Duration::from_secs(15) {
-                self.persistence_write()?;
+        // Fault codes rarely change, no need to spend bandwidth on them every poll.
+        if self.sdl_viewer.connection_state.is_streaming()
+            && self.last_dtc_read.elapsed() > Duration::from_secs(10)
+        {
+            let result = self.sdl_viewer.read_fault_codes().map(|_| ());
+            self.report(result);
+            self.last_dtc_read = Instant::now();
+        }
+
+        if self.page == Page::Explorer && self.sdl_viewer.connection_state.is_streaming() {
+            let result = self.raw_explorer.poll(&mut self.sdl_viewer);
+            self.report(result);
+        }
+
+        let result = self.sdl_viewer.enforce_actuator_safety();
+        self.report(result);
+
+        // Trip meter reset and page gesture
+        match self
+            .el_gestures
+            .update(self.sdl_viewer.engine_context.electric_load)
+        {
+            Some(ElGesture::TripReset) => {
+                self.reset_trip_meter();
                 self.last_write = Instant::now();
             }
+            Some(ElGesture::NextPage) => self.page = self.page.next(),
+            None => {}
+        }
 
-            terminal.draw(|frame| self.render(frame))?;
-            self.handle_crossterm_events(should_simulate)?;
+        // Write to file
+        if self.last_write.elapsed() > Duration::from_secs(15) {
+            self.persistence_write()?;
+            self.last_write = Instant::now();
         }
         Ok(())
     }
@@ -129,15 +568,70 @@ impl App {
     /// - <https://docs.rs/ratatui/latest/ratatui/widgets/index.html>
     /// - <https://github.com/ratatui/ratatui/tree/main/ratatui-widgets/examples>
     fn render(&mut self, frame: &mut Frame) {
+        match self.page {
+            Page::Dashboard => {}
+            Page::Explorer => {
+                let explorer_page = RawExplorerPage::new(&self.raw_explorer);
+                frame.render_widget(explorer_page, frame.area());
+                frame.render_widget(NoEcuOverlay::new(&self.sdl_viewer), frame.area());
+                self.render_status(frame);
+                return;
+            }
+            Page::Graphs => {
+                frame.render_widget(GraphsPage::new(&self.history), frame.area());
+                frame.render_widget(NoEcuOverlay::new(&self.sdl_viewer), frame.area());
+                self.render_status(frame);
+                return;
+            }
+            Page::Dtcs => {
+                frame.render_widget(DtcPage::new(&self.sdl_viewer), frame.area());
+                if let Some(countdown) = &self.clear_codes_countdown {
+                    frame.render_widget(ClearCodesPopup::new(countdown), frame.area());
+                }
+                frame.render_widget(NoEcuOverlay::new(&self.sdl_viewer), frame.area());
+                self.render_status(frame);
+                return;
+            }
+            Page::FreezeFrames => {
+                frame.render_widget(FreezeFramePage::new(&self.freeze_frames), frame.area());
+                self.render_status(frame);
+                return;
+            }
+            Page::Trip => {
+                let trip_page =
+                    TripPage::new(&self.sdl_viewer.engine_context, &self.sdl_viewer.vehicle);
+                frame.render_widget(trip_page, frame.area());
+                self.render_status(frame);
+                return;
+            }
+            Page::Diagnostics => {
+                let diagnostics_page = DiagnosticsPage::new(&self.sdl_viewer);
+                frame.render_widget(diagnostics_page, frame.area());
+                self.render_status(frame);
+                return;
+            }
+            Page::Actuator => {
+                let actuator_page =
+                    ActuatorPage::new(&self.sdl_viewer, self.actuator_message.as_ref());
+                frame.render_widget(actuator_page, frame.area());
+                frame.render_widget(NoEcuOverlay::new(&self.sdl_viewer), frame.area());
+                self.render_status(frame);
+                return;
+            }
+        }
+
         // brand new shrand new
-        let engine_speed_block = EngineSpeedBlock::new(&self.sdl_viewer.engine_context);
+        let engine_speed_block =
+            EngineSpeedBlock::new(&self.sdl_viewer.engine_context, &self.sdl_viewer.vehicle);
         let airflow_block = AirflowBlock::new(&self.sdl_viewer.engine_context);
-        let fuel_ignition_block = FuelIgnitionBlock::new(&self.sdl_viewer.engine_context);
+        let fuel_ignition_block =
+            FuelIgnitionBlock::new(&self.sdl_viewer.engine_context, &self.sdl_viewer.vehicle);
         let vehicle_block = VehicleBlock::new(&self.sdl_viewer.engine_context);
         let throttle_block = ThrottleBlock::new(&self.sdl_viewer.engine_context);
         let temperature_block = TemperatureBlock::new(&self.sdl_viewer.engine_context);
         let electrical_block = ElectricalBlock::new(&self.sdl_viewer.engine_context);
         let flags_block = FlagsBlock::new(&self.sdl_viewer.engine_context);
+        let dtc_block = DtcBlock::new(&self.sdl_viewer);
 
         let layout = Layout::default()
             .direction(Direction::Horizontal)
@@ -149,6 +643,7 @@ impl App {
                 Constraint::Length(9), // engine
                 Constraint::Length(6), // fuel/ignition
                 Constraint::Length(6), // temperatures
+                Constraint::Min(0),    // current fault codes
             ])
             .split(layout[0]);
         let right = Layout::default()
@@ -165,30 +660,42 @@ impl App {
         frame.render_widget(engine_speed_block, left[0]);
         frame.render_widget(fuel_ignition_block, left[1]);
         frame.render_stateful_widget(temperature_block, left[2], &mut self.temperature_state);
+        frame.render_widget(dtc_block, left[3]);
         frame.render_stateful_widget(airflow_block, right[0], &mut self.airflow_state);
         frame.render_widget(throttle_block, right[1]);
         frame.render_widget(electrical_block, right[2]);
         frame.render_widget(vehicle_block, right[3]);
         frame.render_widget(flags_block, right[4]);
 
-        // raw data display
-        /*
-        for (idx, addr) in ObdAddress::iter().enumerate() {
-            let value = self.sdl_viewer.raw_data.get(&addr).unwrap();
-            let area = Rect::new(0, idx as u16, addr.to_string().len() as u16, 1);
-            let area_value = Rect::new(30, idx as u16, 10, 1);
-            frame.render_widget(Paragraph::new(format!("{}:", addr)), area);
-            frame.render_widget(Paragraph::new(value.to_string()), area_value);
+        if let Some(countdown) = &self.clear_codes_countdown {
+            frame.render_widget(ClearCodesPopup::new(countdown), frame.area());
         }
-        */
+        if self.unknown_ecu_warning
+            && let Some(ecu_id) = self.sdl_viewer.ecu_id
+        {
+            let ecu_id = format_ecu_id(ecu_id);
+            frame.render_widget(UnknownEcuPopup::new(&ecu_id), frame.area());
+        }
+        frame.render_widget(NoEcuOverlay::new(&self.sdl_viewer), frame.area());
+        self.render_status(frame);
     }
 
     /// Reads the crossterm events and updates the state of [`App`].
     ///
     /// If your application needs to perform work in between handling events, you can use the
     /// [`event::poll`] function to check if there are any events available with a timeout.
-    fn handle_crossterm_events(&mut self, should_simulate: bool) -> Result<()> {
-        if poll(Duration::from_millis(if should_simulate { 100 } else { 0 }))? {
+    fn handle_crossterm_events(&mut self) -> Result<()> {
+        // back off a little while the ECU is erroring instead of spinning on a dead port
+        let timeout = if self.simulator.is_some()
+            || self.replay.is_some()
+            || self.sdl_error.is_some()
+            || !self.sdl_viewer.connection_state.is_streaming()
+        {
+            100
+        } else {
+            0
+        };
+        if poll(Duration::from_millis(timeout))? {
             match event::read()? {
                 // it's important to check KeyEventKind::Press to avoid handling key release events
                 Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
@@ -205,11 +712,177 @@ impl App {
         match (key.modifiers, key.code) {
             (_, KeyCode::Esc | KeyCode::Char('q'))
             | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
+            (_, KeyCode::Char('x')) => self.clear_codes_countdown = None,
+            (_, KeyCode::Char('u')) => self.unknown_ecu_warning = false,
+            (_, KeyCode::Char('a')) => {
+                self.page = self.page.toggle(Page::Actuator);
+                self.actuator_message = None;
+            }
+            (_, KeyCode::Char('d')) => self.page = self.page.toggle(Page::Diagnostics),
+            (_, KeyCode::Char('e')) => self.page = self.page.toggle(Page::Explorer),
+            (_, KeyCode::Char('g')) => self.page = self.page.toggle(Page::Graphs),
+            (_, KeyCode::Char('f')) => self.page = self.page.toggle(Page::Dtcs),
+            (_, KeyCode::Char('t')) => self.page = self.page.toggle(Page::Trip),
+            (_, KeyCode::Char('n')) => self.page = self.page.next(),
+            (_, KeyCode::Char('N')) => self.page = self.page.previous(),
+            (_, KeyCode::Char('z')) => self.page = self.page.toggle(Page::FreezeFrames),
+            (_, KeyCode::Char('m')) => self.capture_frame(),
+            (_, KeyCode::Up) if self.page == Page::FreezeFrames => {
+                self.freeze_frames.select_newer()
+            }
+            (_, KeyCode::Down) if self.page == Page::FreezeFrames => {
+                self.freeze_frames.select_older()
+            }
+            (_, KeyCode::Tab) if self.page == Page::Graphs => self.history.next_primary(),
+            (_, KeyCode::BackTab) if self.page == Page::Graphs => self.history.previous_primary(),
+            (_, KeyCode::Char('o')) if self.page == Page::Graphs => self.history.next_overlay(),
+            (_, KeyCode::Char('w')) if self.page == Page::Graphs => self.history.next_window(),
+            (_, KeyCode::Char('p')) if self.page == Page::Explorer => {
+                self.raw_explorer.toggle_pin(self.raw_explorer.cursor);
+            }
+            (_, KeyCode::Left) if self.page == Page::Explorer => {
+                self.raw_explorer.cursor = self.raw_explorer.cursor.wrapping_sub(1);
+            }
+            (_, KeyCode::Right) if self.page == Page::Explorer => {
+                self.raw_explorer.cursor = self.raw_explorer.cursor.wrapping_add(1);
+            }
+            (_, KeyCode::Up) if self.page == Page::Explorer => {
+                self.raw_explorer.cursor = self.raw_explorer.cursor.wrapping_sub(16);
+            }
+            (_, KeyCode::Down) if self.page == Page::Explorer => {
+                self.raw_explorer.cursor = self.raw_explorer.cursor.wrapping_add(16);
+            }
+            (_, KeyCode::Char('s')) if self.page == Page::Actuator => {
+                self.actuator_message = self.sdl_viewer.stop_actuator_test().err();
+            }
+            (_, KeyCode::Char(c @ '1'..='9')) if self.page == Page::Actuator => {
+                let idx = c as usize - '1' as usize;
+                if let Some(test) = ActuatorTest::iter().nth(idx) {
+                    self.toggle_actuator_test(test);
+                }
+            }
+            (_, KeyCode::Enter) if self.clear_codes_countdown.is_some() => self.clear_fault_codes(),
+            (_, KeyCode::Char(' ')) if self.replay.is_some() => {
+                self.control_replay(|replay, _| replay.toggle_pause());
+            }
+            (_, KeyCode::Left) if self.replay.is_some() => self.control_replay(|replay, viewer| {
+                replay.seek(replay.position().saturating_sub(SEEK_STEP), viewer);
+            }),
+            (_, KeyCode::Right) if self.replay.is_some() => {
+                self.control_replay(|replay, viewer| {
+                    replay.seek(replay.position() + SEEK_STEP, viewer);
+                })
+            }
+            (_, KeyCode::Char('[')) => self.control_replay(|replay, _| replay.slower()),
+            (_, KeyCode::Char(']')) => self.control_replay(|replay, _| replay.faster()),
+            (_, KeyCode::Char(',')) => self.control_replay(Replay::step_back),
+            (_, KeyCode::Char('.')) => self.control_replay(Replay::step_forward),
+            (_, KeyCode::Up) if self.simulator.is_some() => {
+                self.drive(|engine| engine.throttle = (engine.throttle + 0.1).min(1.0));
+            }
+            (_, KeyCode::Down) if self.simulator.is_some() => {
+                self.drive(|engine| engine.throttle = (engine.throttle - 0.1).max(0.0));
+            }
+            (_, KeyCode::Char(' ')) => self.drive(|engine| engine.throttle = 0.0),
+            (_, KeyCode::Char('c')) => self.drive(|engine| engine.ac = !engine.ac),
+            (_, KeyCode::Char('l')) => self.drive(|engine| engine.lights = !engine.lights),
             // Add other key handlers here.
             _ => {}
         }
     }
 
+    /// Change the simulated engine's controls, a no-op unless simulating.
+    fn drive(&mut self, control: impl FnOnce(&mut EngineSimulator)) {
+        if let Some(simulator) = &self.simulator {
+            control(&mut simulator.lock().unwrap());
+        }
+    }
+
+    fn render_status(&self, frame: &mut Frame) {
+        if let Some(replay) = &self.replay {
+            frame.render_widget(ReplayBar::new(replay), frame.area());
+        }
+        frame.render_widget(self.status_bar(), frame.area());
+    }
+
+    /// Control playback, a no-op unless replaying.
+    fn control_replay(&mut self, control: impl FnOnce(&mut Replay, &mut SuzukiSdlViewer)) {
+        if let Some(replay) = &mut self.replay {
+            control(replay, &mut self.sdl_viewer);
+        }
+    }
+
+    fn status_bar(&self) -> StatusBar<'_> {
+        StatusBar::new(self.sdl_error.as_ref())
+            .recorder_error(self.recorder_error.as_ref())
+            .export_error(self.export_error.as_ref())
+            .freeze_frame_error(self.freeze_frame_error.as_ref())
+    }
+
+    /// Log the poll that just came in. A failing recorder is dropped rather than retried, so a
+    /// full or pulled SD card doesn't get in the way of the dashboard.
+    fn record_poll(&mut self, resumed: bool) {
+        let (Some(recorder), Some(ecu_id)) = (&mut self.recorder, self.sdl_viewer.ecu_id) else {
+            return;
+        };
+        let record =
+            LogRecord::new(SystemTime::now(), ecu_id, &self.sdl_viewer.raw_data).resumed(resumed);
+        // the same clock fuel and distance were integrated on
+        let polled = self
+            .sdl_viewer
+            .engine_context
+            .last_poll
+            .unwrap_or_else(Instant::now);
+        if let Err(err) = recorder.record(&record, polled) {
+            self.recorder = None;
+            self.recorder_error = Some(err);
+        }
+    }
+
+    /// Write the poll that just came in to the CSV export, dropped on error like the recorder.
+    fn export_poll(&mut self) {
+        let Some(exporter) = &mut self.exporter else {
+            return;
+        };
+        if let Err(err) = exporter.write(SystemTime::now(), &self.sdl_viewer.engine_context) {
+            self.exporter = None;
+            self.export_error = Some(err);
+        }
+    }
+
+    /// Capture a freeze frame for any alert that just came up. Errors saving are shown, the
+    /// frames are still kept for this run. A poll with nothing to save leaves the last error up.
+    fn capture_alerts(&mut self) {
+        let result = self.freeze_frames.update(
+            SystemTime::now(),
+            self.sdl_viewer.ecu_id,
+            &self.sdl_viewer.engine_context,
+            &self.sdl_viewer.raw_data,
+        );
+        if let Err(err) = result {
+            self.freeze_frame_error = Some(err);
+        }
+    }
+
+    /// Capture a freeze frame of what is shown, at the recorded time when replaying.
+    fn capture_frame(&mut self) {
+        let at = self
+            .replay
+            .as_ref()
+            .and_then(Replay::recorded_at)
+            .unwrap_or_else(SystemTime::now);
+        let frame = FreezeFrame::new(
+            Trigger::Manual,
+            at,
+            self.sdl_viewer.ecu_id,
+            &self.sdl_viewer.engine_context,
+            &self.sdl_viewer.raw_data,
+        );
+        if let Err(err) = self.freeze_frames.capture(frame) {
+            self.freeze_frame_error = Some(err);
+        }
+    }
+
     /// Set running to false to quit the application.
     fn quit(&mut self) {
         self.running = false;
@@ -0,0 +1,75 @@
+/// Toggles of a quick EL burst that moves to the next page, well short of the trip reset's six.
+pub const PAGE_GESTURE_TOGGLES: u8 = 2;
+
+/// What fills the screen. Polling carries on whatever the page.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Page {
+    #[default]
+    Dashboard,
+    Explorer,
+    Graphs,
+    Dtcs,
+    FreezeFrames,
+    Trip,
+    Diagnostics,
+    /// Only opened by key, so an EL gesture never lands on active tests.
+    Actuator,
+}
+
+/// Pages stepped through in order by `n`/`N` and the EL gesture.
+pub const PAGE_CYCLE: [Page; 7] = [
+    Page::Dashboard,
+    Page::Explorer,
+    Page::Graphs,
+    Page::Dtcs,
+    Page::FreezeFrames,
+    Page::Trip,
+    Page::Diagnostics,
+];
+
+impl Page {
+    pub fn next(self) -> Self {
+        self.step(1)
+    }
+
+    pub fn previous(self) -> Self {
+        self.step(PAGE_CYCLE.len() - 1)
+    }
+
+    /// `page`, or back to the dashboard if it is the one shown already.
+    pub fn toggle(self, page: Page) -> Self {
+        if self == page { Page::Dashboard } else { page }
+    }
+
+    fn step(self, by: usize) -> Self {
+        // pages outside the cycle count as the dashboard
+        let index = PAGE_CYCLE
+            .iter()
+            .position(|page| *page == self)
+            .unwrap_or_default();
+        PAGE_CYCLE[(index + by) % PAGE_CYCLE.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::page::{PAGE_CYCLE, Page};
+
+    #[test]
+    fn test_navigation() {
+        let mut page = Page::Dashboard;
+        let mut seen = Vec::new();
+        for _ in 0..PAGE_CYCLE.len() {
+            seen.push(page);
+            page = page.next();
+        }
+        assert_eq!(seen, PAGE_CYCLE);
+        assert_eq!(page, Page::Dashboard);
+        assert_eq!(page.previous(), Page::Diagnostics);
+        assert_eq!(Page::Actuator.next(), Page::Explorer);
+
+        assert_eq!(Page::Dashboard.toggle(Page::Graphs), Page::Graphs);
+        assert_eq!(Page::Graphs.toggle(Page::Graphs), Page::Dashboard);
+        assert_eq!(Page::Trip.toggle(Page::Graphs), Page::Graphs);
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use crate::sdl::ObdAddress;
+
+/// How often the slow group rides along with a fast poll.
+pub const SLOW_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Live parameters grouped by how quickly they change while driving.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, Display)]
+pub enum PollGroup {
+    /// Follows the throttle: RPM, TPS, MAP, injector PW, ignition advance and speed. Polled
+    /// every time, together with the status flags so quick EL toggles aren't missed.
+    Fast,
+
+    /// Temperatures, BARO, battery, idle control and radiator fan. Take seconds to change.
+    Slow,
+}
+
+impl PollGroup {
+    /// Group an address belongs to, `None` for fault code bytes which are read separately.
+    pub fn of(addr: ObdAddress) -> Option<PollGroup> {
+        match addr {
+            ObdAddress::RpmHigh
+            | ObdAddress::RpmLow
+            | ObdAddress::VehicleSpeedSensor
+            | ObdAddress::TpsAngle
+            | ObdAddress::TpsVoltage
+            | ObdAddress::InjectorPulseWidthHigh
+            | ObdAddress::InjectorPulseWidthLow
+            | ObdAddress::IgnitionAdvance
+            | ObdAddress::ManifoldAbsolutePressure
+            | ObdAddress::StatusFlags => Some(PollGroup::Fast),
+            ObdAddress::TargetIdle
+            | ObdAddress::EngineCoolantTemperature
+            | ObdAddress::IntakeAirTemperature
+            | ObdAddress::BarometricPressure
+            | ObdAddress::IscFlowDuty
+            | ObdAddress::BatteryVoltage
+            | ObdAddress::RadiatorFan => Some(PollGroup::Slow),
+            ObdAddress::FaultCodes1
+            | ObdAddress::FaultCodes2
+            | ObdAddress::FaultCodes3
+            | ObdAddress::FaultCodes4
+            | ObdAddress::FaultCodes5
+            | ObdAddress::FaultCodes6 => None,
+        }
+    }
+
+    pub fn addresses(&self) -> Vec<ObdAddress> {
+        ObdAddress::iter()
+            .filter(|addr| PollGroup::of(*addr) == Some(*self))
+            .collect()
+    }
+}
+
+/// Decides which addresses go into the next Data request. The fast group is in every request,
+/// the slow group is added once per [`SLOW_POLL_INTERVAL`]. Interleaving by time rather than by
+/// poll count keeps slow values equally fresh no matter how quick the link is.
+#[derive(Debug, Clone)]
+pub struct PollScheduler {
+    slow_interval: Duration,
+    last_slow_poll: Option<Instant>,
+}
+
+impl Default for PollScheduler {
+    fn default() -> Self {
+        Self::new(SLOW_POLL_INTERVAL)
+    }
+}
+
+impl PollScheduler {
+    pub fn new(slow_interval: Duration) -> Self {
+        Self {
+            slow_interval,
+            last_slow_poll: None,
+        }
+    }
+
+    /// Addresses for the next request, in address order.
+    pub fn next_addresses(&self, now: Instant) -> Vec<ObdAddress> {
+        let slow_due = self
+            .last_slow_poll
+            .is_none_or(|last| now.duration_since(last) >= self.slow_interval);
+        ObdAddress::iter()
+            .filter(|addr| match PollGroup::of(*addr) {
+                Some(PollGroup::Fast) => true,
+                Some(PollGroup::Slow) => slow_due,
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Record a successful poll of `addrs`.
+    pub fn polled(&mut self, addrs: &[ObdAddress], now: Instant) {
+        if addrs
+            .iter()
+            .any(|addr| PollGroup::of(*addr) == Some(PollGroup::Slow))
+        {
+            self.last_slow_poll = Some(now);
+        }
+    }
+
+    /// Poll everything on the next request, e.g. after reconnecting.
+    pub fn reset(&mut self) {
+        self.last_slow_poll = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        polling::{PollGroup, PollScheduler},
+        sdl::ObdAddress,
+    };
+
+    #[test]
+    fn test_groups_cover_live_addresses() {
+        let live: Vec<ObdAddress> = ObdAddress::iter().filter(|v| !v.is_fault_code()).collect();
+        let fast = PollGroup::Fast.addresses();
+        let slow = PollGroup::Slow.addresses();
+        assert_eq!(fast.len() + slow.len(), live.len());
+        assert!(
+            live.iter()
+                .all(|addr| fast.contains(addr) ^ slow.contains(addr))
+        );
+        assert!(fast.contains(&ObdAddress::RpmHigh));
+        assert!(fast.contains(&ObdAddress::StatusFlags));
+        assert!(slow.contains(&ObdAddress::EngineCoolantTemperature));
+    }
+
+    #[test]
+    fn test_slow_group_interleaved() {
+        let mut scheduler = PollScheduler::new(Duration::from_secs(1));
+        let start = Instant::now();
+        let all = scheduler.next_addresses(start);
+        assert_eq!(all.len(), 17);
+        scheduler.polled(&all, start);
+
+        let fast = scheduler.next_addresses(start + Duration::from_millis(100));
+        assert_eq!(fast, PollGroup::Fast.addresses());
+        scheduler.polled(&fast, start + Duration::from_millis(100));
+
+        let later = start + Duration::from_secs(1);
+        assert_eq!(scheduler.next_addresses(later), all);
+
+        scheduler.reset();
+        assert_eq!(scheduler.next_addresses(start), all);
+    }
+}
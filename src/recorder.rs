@@ -0,0 +1,520 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::sdl::ObdAddress;
+
+/// Start of every log file, followed by [`LOG_VERSION`].
+pub const LOG_MAGIC: &[u8; 4] = b"SZRL";
+pub const LOG_VERSION: u8 = 1;
+pub const LOG_EXTENSION: &str = "rawlog";
+
+/// Records are flushed to the SD card at least this often, a power cut loses at most this much.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One poll worth of raw bytes, as written to the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// Wall-clock time of the poll, kept to the millisecond. Only for showing, the clock can be
+    /// stepped by NTP mid-drive on a Pi without an RTC.
+    pub at: SystemTime,
+    /// Time of the poll since the session started on a monotonic clock, what replay runs on.
+    /// Set by [`Recorder::record`].
+    pub elapsed: Duration,
+    pub ecu_id: [u8; 2],
+    /// First poll after (re)connecting to the ECU. Fuel and distance aren't integrated over the
+    /// gap before it, so a replay has to skip it too.
+    pub resumed: bool,
+    /// Address and raw value pairs, in address order.
+    pub values: Vec<(u8, u8)>,
+}
+
+const FLAG_RESUMED: u8 = 0x01;
+
+/// Bytes of a record payload before the address and value pairs.
+const RECORD_HEADER_SIZE: usize = 15;
+
+impl LogRecord {
+    pub fn new(at: SystemTime, ecu_id: [u8; 2], raw_data: &HashMap<ObdAddress, u8>) -> Self {
+        let mut values: Vec<(u8, u8)> = raw_data
+            .iter()
+            .map(|(addr, value)| (*addr as u8, *value))
+            .collect();
+        values.sort();
+        Self {
+            at,
+            elapsed: Duration::ZERO,
+            ecu_id,
+            resumed: false,
+            values,
+        }
+    }
+
+    pub fn resumed(mut self, resumed: bool) -> Self {
+        self.resumed = resumed;
+        self
+    }
+
+    pub fn elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = elapsed;
+        self
+    }
+
+    /// Values for addresses this version knows about, anything else is skipped.
+    pub fn raw_data(&self) -> impl Iterator<Item = (ObdAddress, u8)> + '_ {
+        self.values.iter().filter_map(|(addr, value)| {
+            ObdAddress::from_repr(*addr as usize).map(|addr| (addr, *value))
+        })
+    }
+
+    /// `[len][millis since epoch (8)][ECU ID (2)][flags][elapsed millis (4)][addr, value]...`
+    /// `[CRC-32 of the rest (4)]`, little endian. The length and CRC let a reader spot a record
+    /// cut short by a power loss.
+    fn to_bytes(&self) -> Vec<u8> {
+        let millis = self
+            .at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let elapsed = self.elapsed.as_millis() as u32;
+        let mut payload = Vec::with_capacity(RECORD_HEADER_SIZE + self.values.len() * 2);
+        payload.extend(millis.to_le_bytes());
+        payload.extend(self.ecu_id);
+        payload.push(if self.resumed { FLAG_RESUMED } else { 0 });
+        payload.extend(elapsed.to_le_bytes());
+        for (addr, value) in &self.values {
+            payload.extend([*addr, *value]);
+        }
+
+        let mut bytes = Vec::with_capacity(payload.len() + 5);
+        bytes.push(payload.len() as u8);
+        bytes.extend(&payload);
+        bytes.extend(crc32(&payload).to_le_bytes());
+        bytes
+    }
+
+    fn from_payload(payload: &[u8]) -> Option<Self> {
+        if payload.len() < RECORD_HEADER_SIZE
+            || !(payload.len() - RECORD_HEADER_SIZE).is_multiple_of(2)
+        {
+            return None;
+        }
+        let millis = u64::from_le_bytes(payload[..8].try_into().ok()?);
+        let elapsed = u32::from_le_bytes(payload[11..15].try_into().ok()?);
+        Some(Self {
+            at: UNIX_EPOCH + Duration::from_millis(millis),
+            elapsed: Duration::from_millis(elapsed as u64),
+            ecu_id: [payload[8], payload[9]],
+            resumed: payload[10] & FLAG_RESUMED != 0,
+            values: payload[RECORD_HEADER_SIZE..]
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+        })
+    }
+}
+
+/// CRC-32 (IEEE), bit by bit. Records are tiny so a table isn't worth it.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Contents of one log file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionLog {
+    pub records: Vec<LogRecord>,
+    /// The file ended in a partial or corrupt record, e.g. power was cut mid-write. Everything
+    /// before it is still in `records`.
+    pub truncated: bool,
+}
+
+impl SessionLog {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let Some(mut rest) = bytes.strip_prefix(LOG_MAGIC.as_slice()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a raw data log",
+            ));
+        };
+        match rest.split_first() {
+            Some((&LOG_VERSION, records)) => rest = records,
+            // a power cut right after creating the file leaves just the magic
+            None => {
+                return Ok(Self {
+                    records: Vec::new(),
+                    truncated: true,
+                });
+            }
+            Some((version, _)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported raw data log version {version}"),
+                ));
+            }
+        };
+
+        let mut log = Self::default();
+        while let Some((&len, after_len)) = rest.split_first() {
+            let len = len as usize;
+            let record = after_len.get(..len + 4).and_then(|record| {
+                let (payload, crc) = record.split_at(len);
+                (crc32(payload).to_le_bytes() == crc)
+                    .then(|| LogRecord::from_payload(payload))
+                    .flatten()
+            });
+            let Some(record) = record else {
+                log.truncated = true;
+                break;
+            };
+            log.records.push(record);
+            rest = &after_len[len + 4..];
+        }
+        Ok(log)
+    }
+}
+
+/// Appends every poll to log files in a directory. A file is closed and the next one started
+/// once it would grow past `max_file_size`, the oldest files are deleted so no more than
+/// `max_files` are kept. Files of one session share a session number in their name:
+/// `session-<number>-<part>.rawlog`. The number is the start time in unix seconds, or one past
+/// the newest session if the clock is behind it, e.g. restarted from the same time after a
+/// power cut on a Pi without an RTC. Names never clash and sort in recording order.
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    file: Option<File>,
+    file_size: u64,
+    session: Option<u64>,
+    /// When the first poll of the session was taken, on the monotonic clock.
+    started: Option<Instant>,
+    part: u32,
+    last_sync: Option<Instant>,
+}
+
+impl Recorder {
+    pub fn new(dir: impl Into<PathBuf>, max_file_size: u64, max_files: usize) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            max_file_size,
+            max_files,
+            file: None,
+            file_size: 0,
+            session: None,
+            started: None,
+            part: 0,
+            last_sync: None,
+        })
+    }
+
+    /// File currently written to, if any.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.session
+            .map(|session| self.file_path(session, self.part))
+    }
+
+    fn file_path(&self, session: u64, part: u32) -> PathBuf {
+        self.dir
+            .join(format!("session-{session}-{part:03}.{LOG_EXTENSION}"))
+    }
+
+    /// Append `record` of the poll taken at `polled`, its elapsed time is counted from the first
+    /// poll recorded.
+    pub fn record(&mut self, record: &LogRecord, polled: Instant) -> io::Result<()> {
+        let started = *self.started.get_or_insert(polled);
+        let record = record.clone().elapsed(polled.duration_since(started));
+        let bytes = record.to_bytes();
+        if self.file.is_none() || self.file_size + bytes.len() as u64 > self.max_file_size {
+            self.next_file(record.at)?;
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        // a single write per record, so a power cut can only tear the last one
+        file.write_all(&bytes)?;
+        self.file_size += bytes.len() as u64;
+
+        let now = Instant::now();
+        if self
+            .last_sync
+            .is_none_or(|last_sync| now.duration_since(last_sync) >= SYNC_INTERVAL)
+        {
+            file.sync_data()?;
+            self.last_sync = Some(now);
+        }
+        Ok(())
+    }
+
+    fn next_file(&mut self, at: SystemTime) -> io::Result<()> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        match self.session {
+            Some(_) => self.part += 1,
+            None => {
+                let started = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                let newest = log_files(&self.dir)?
+                    .iter()
+                    .filter_map(|path| session_number(path))
+                    .max();
+                self.session = Some(newest.map_or(started, |newest| started.max(newest + 1)));
+            }
+        }
+        let path = self.path().unwrap_or_default();
+        let mut file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)?;
+        file.write_all(LOG_MAGIC)?;
+        file.write_all(&[LOG_VERSION])?;
+        file.sync_all()?;
+        // make the new directory entry itself durable too
+        File::open(&self.dir)?.sync_all()?;
+        self.file = Some(file);
+        self.file_size = LOG_MAGIC.len() as u64 + 1;
+        self.last_sync = Some(Instant::now());
+        self.prune()
+    }
+
+    /// Delete the oldest log files beyond `max_files`.
+    fn prune(&self) -> io::Result<()> {
+        let mut logs = log_files(&self.dir)?;
+        let excess = logs.len().saturating_sub(self.max_files);
+        for path in logs.drain(..excess) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Session number in the name of a log file, see [`Recorder`].
+fn session_number(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_string_lossy();
+    let (session, _part) = name.strip_prefix("session-")?.split_once('-')?;
+    session.parse().ok()
+}
+
+/// Log files in `dir`, oldest first by session number, then by part.
+pub fn log_files(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == LOG_EXTENSION)
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("session-"))
+        })
+        .collect();
+    logs.sort_by_cached_key(|path| (session_number(path), path.clone()));
+    Ok(logs)
+}
+
+/// Every part of the session `path` belongs to, in order.
+pub fn session_files(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let session = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .and_then(|name| {
+            let (session, _part) = name.rsplit_once('-')?;
+            session
+                .starts_with("session-")
+                .then(|| format!("{session}-"))
+        });
+    let Some(session) = session else {
+        // renamed or copied by hand, play it on its own
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Ok(log_files(dir)?
+        .into_iter()
+        .filter(|file| {
+            file.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&session))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs,
+        path::PathBuf,
+        time::{Duration, Instant, UNIX_EPOCH},
+    };
+
+    use crate::{
+        recorder::{LogRecord, Recorder, SessionLog, crc32, log_files, session_files},
+        sdl::ObdAddress,
+    };
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("suzui-recorder-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn record(millis: u64, rpm_high: u8) -> LogRecord {
+        let raw_data = HashMap::from([
+            (ObdAddress::RpmHigh, rpm_high),
+            (ObdAddress::RpmLow, 0x10),
+            (ObdAddress::FaultCodes1, 0),
+        ]);
+        LogRecord::new(
+            UNIX_EPOCH + Duration::from_millis(millis),
+            [0x31, 0x09],
+            &raw_data,
+        )
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = scratch_dir("round-trip");
+        let mut recorder = Recorder::new(&dir, 1 << 20, 10).unwrap();
+        let start = Instant::now();
+        let records: Vec<_> = (0..3)
+            .map(|i| record(1_700_000_000_000 + i, i as u8).resumed(i == 0))
+            .collect();
+        for (i, record) in records.iter().enumerate() {
+            recorder
+                .record(record, start + Duration::from_millis(250 * i as u64))
+                .unwrap();
+        }
+        let records: Vec<_> = records
+            .into_iter()
+            .enumerate()
+            .map(|(i, record)| record.elapsed(Duration::from_millis(250 * i as u64)))
+            .collect();
+        let path = recorder.path().unwrap();
+        assert!(path.ends_with("session-1700000000-000.rawlog"));
+
+        let log = SessionLog::read(&path).unwrap();
+        assert_eq!(log.records, records);
+        assert!(!log.truncated);
+        assert_eq!(
+            log.records[1].raw_data().collect::<Vec<_>>(),
+            vec![
+                (ObdAddress::FaultCodes1, 0),
+                (ObdAddress::RpmHigh, 1),
+                (ObdAddress::RpmLow, 0x10)
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_torn_write_keeps_earlier_records() {
+        let dir = scratch_dir("torn");
+        let mut recorder = Recorder::new(&dir, 1 << 20, 10).unwrap();
+        let start = Instant::now();
+        recorder.record(&record(1000, 1), start).unwrap();
+        recorder
+            .record(&record(2000, 2), start + Duration::from_secs(1))
+            .unwrap();
+        let bytes = fs::read(recorder.path().unwrap()).unwrap();
+
+        let torn = SessionLog::from_bytes(&bytes[..bytes.len() - 3]).unwrap();
+        assert_eq!(torn.records, vec![record(1000, 1).elapsed(Duration::ZERO)]);
+        assert!(torn.truncated);
+
+        let mut corrupt = bytes.clone();
+        *corrupt.last_mut().unwrap() ^= 0xff;
+        assert_eq!(SessionLog::from_bytes(&corrupt).unwrap().records.len(), 1);
+
+        assert!(SessionLog::from_bytes(b"garbage").is_err());
+        assert!(SessionLog::from_bytes(b"SZRL").unwrap().truncated);
+        assert!(SessionLog::from_bytes(b"SZRL\x02").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_and_pruning() {
+        let dir = scratch_dir("rotation");
+        // header plus two records per file
+        let record_size = 1 + 8 + 2 + 1 + 4 + 3 * 2 + 4;
+        let mut recorder = Recorder::new(&dir, 5 + 2 * record_size, 2).unwrap();
+        let start = Instant::now();
+        for i in 0..7 {
+            recorder
+                .record(
+                    &record(1_000_000 + i, i as u8),
+                    start + Duration::from_secs(i),
+                )
+                .unwrap();
+        }
+        let files = log_files(&dir).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["session-1000-002.rawlog", "session-1000-003.rawlog"]
+        );
+        let last = SessionLog::read(&files[1]).unwrap();
+        // the session clock runs on across files
+        assert_eq!(
+            last.records,
+            vec![record(1_000_006, 6).elapsed(Duration::from_secs(6))]
+        );
+
+        let mut other = Recorder::new(&dir, 1 << 20, 10).unwrap();
+        other.record(&record(2_000_000, 0), start).unwrap();
+        assert_eq!(session_files(&files[1]).unwrap(), files);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_clock_behind_newest_session() {
+        let dir = scratch_dir("clock-behind");
+        let start = Instant::now();
+        let session = |millis| {
+            let mut recorder = Recorder::new(&dir, 1 << 20, 2).unwrap();
+            recorder.record(&record(millis, 0), start).unwrap();
+            recorder.path().unwrap()
+        };
+        session(5_000_000);
+        // the clock restarted from the same time, then from before it
+        let same = session(5_000_000);
+        let behind = session(1_000_000);
+        assert!(same.ends_with("session-5001-000.rawlog"));
+        assert!(behind.ends_with("session-5002-000.rawlog"));
+        // the oldest recording went, not the one with the earliest clock
+        assert_eq!(log_files(&dir).unwrap(), vec![same, behind]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
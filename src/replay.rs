@@ -0,0 +1,391 @@
+use std::{
+    io,
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    connection::ConnectionState,
+    recorder::{LogRecord, SessionLog, session_files},
+    sdl::{EngineContext, SuzukiSdlViewer},
+    toggle_detector::{ElGesture, ElGestures},
+};
+
+/// Playback speeds to step through, from a quarter to 16 times real time.
+pub const REPLAY_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+const REAL_TIME: usize = 2;
+
+/// How far one seek moves.
+pub const SEEK_STEP: Duration = Duration::from_secs(10);
+
+/// Plays a recorded session back through [`SuzukiSdlViewer::update_processed_data_at`]. Every
+/// record is processed at the time it was recorded, not when it is shown, so fuel and distance
+/// come out the same at any speed. That time is the monotonic [`LogRecord::elapsed`], the wall
+/// clock is only shown. Totals are integrated record by record, so seeking back starts over
+/// from the first one. Trip resets by toggling the lights are replayed too, totals start from
+/// zero.
+#[derive(Debug)]
+pub struct Replay {
+    records: Vec<LogRecord>,
+    /// Stands in for the time of the first record, later ones are offset from it.
+    base: Instant,
+    /// Records before this one have been processed.
+    next: usize,
+    /// Playback clock, time since the first record.
+    position: Duration,
+    speed: usize,
+    paused: bool,
+    last_tick: Option<Instant>,
+    el_gestures: ElGestures,
+    /// Some file of the session ended in a torn record.
+    pub truncated: bool,
+}
+
+impl Replay {
+    pub fn new(records: Vec<LogRecord>) -> Self {
+        Self {
+            records,
+            base: Instant::now(),
+            next: 0,
+            position: Duration::ZERO,
+            speed: REAL_TIME,
+            paused: false,
+            last_tick: None,
+            el_gestures: ElGestures::default(),
+            truncated: false,
+        }
+    }
+
+    /// Load the session `path` is part of, every part of it.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut records = Vec::new();
+        let mut truncated = false;
+        for file in session_files(&path)? {
+            let log = SessionLog::read(file)?;
+            records.extend(log.records);
+            truncated |= log.truncated;
+        }
+        if records.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no records in {}", path.as_ref().display()),
+            ));
+        }
+        let mut replay = Self::new(records);
+        replay.truncated = truncated;
+        Ok(replay)
+    }
+
+    fn offset(&self, index: usize) -> Duration {
+        self.records[index]
+            .elapsed
+            .saturating_sub(self.records[0].elapsed)
+    }
+
+    /// Length of the recording.
+    pub fn duration(&self) -> Duration {
+        match self.records.len() {
+            0 => Duration::ZERO,
+            len => self.offset(len - 1),
+        }
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    pub fn speed(&self) -> f64 {
+        REPLAY_SPEEDS[self.speed]
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(REPLAY_SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Wall-clock time of the record shown.
+    pub fn recorded_at(&self) -> Option<SystemTime> {
+        self.next.checked_sub(1).map(|index| self.records[index].at)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next == self.records.len()
+    }
+
+    /// Move the playback clock on by the real time since the last tick and process every record
+    /// it passed.
+    pub fn tick(&mut self, now: Instant, viewer: &mut SuzukiSdlViewer) {
+        let elapsed = self
+            .last_tick
+            .map(|last_tick| now.duration_since(last_tick))
+            .unwrap_or_default();
+        self.last_tick = Some(now);
+        if !self.paused {
+            self.position = (self.position + elapsed.mul_f64(self.speed())).min(self.duration());
+        }
+        self.catch_up(viewer);
+    }
+
+    /// Jump to `position` into the recording.
+    pub fn seek(&mut self, position: Duration, viewer: &mut SuzukiSdlViewer) {
+        let position = position.min(self.duration());
+        if self.next == 0 || self.offset(self.next - 1) > position {
+            self.restart(viewer);
+        }
+        self.position = position;
+        self.catch_up(viewer);
+    }
+
+    /// Pause and process just the next record.
+    pub fn step_forward(&mut self, viewer: &mut SuzukiSdlViewer) {
+        self.paused = true;
+        if self.next < self.records.len() {
+            self.seek(self.offset(self.next), viewer);
+        }
+    }
+
+    /// Pause and go back to the record before the one shown.
+    pub fn step_back(&mut self, viewer: &mut SuzukiSdlViewer) {
+        self.paused = true;
+        if self.next >= 2 {
+            self.seek(self.offset(self.next - 2), viewer);
+        }
+    }
+
+    /// Forget everything processed so far, totals included.
+    fn restart(&mut self, viewer: &mut SuzukiSdlViewer) {
+        self.next = 0;
+        self.el_gestures = ElGestures::default();
+        viewer.engine_context = EngineContext::default();
+        viewer.connection_state = ConnectionState::Streaming;
+    }
+
+    fn catch_up(&mut self, viewer: &mut SuzukiSdlViewer) {
+        if self.next == 0 {
+            self.restart(viewer);
+        }
+        while self.next < self.records.len() && self.offset(self.next) <= self.position {
+            self.process(self.next, viewer);
+            self.next += 1;
+        }
+    }
+
+    fn process(&mut self, index: usize, viewer: &mut SuzukiSdlViewer) {
+        let at = self.base + self.offset(index);
+        let record = &self.records[index];
+        if viewer.ecu_id != Some(record.ecu_id) {
+            viewer.ecu_id = Some(record.ecu_id);
+            viewer.ecu = viewer.lookup_ecu(record.ecu_id);
+        }
+        viewer.raw_data.extend(record.raw_data());
+        if record.resumed {
+            viewer.engine_context.last_poll = None;
+        }
+        viewer.update_processed_data_at(at);
+        viewer.last_data = Some(at);
+        // page gestures were for the screen back then, only the trip reset carries over
+        if self
+            .el_gestures
+            .update_at(viewer.engine_context.electric_load, at)
+            == Some(ElGesture::TripReset)
+        {
+            viewer.engine_context.reset_trip();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        emulator::EcuEmulator,
+        recorder::LogRecord,
+        replay::Replay,
+        sdl::{EngineContext, ObdAddress, SuzukiSdlViewer},
+        simulator::EngineSimulator,
+    };
+
+    /// A drive polled at an uneven rate, as recorded.
+    fn drive() -> Vec<LogRecord> {
+        let mut engine = EngineSimulator::warm(25.0);
+        let mut ecu = EcuEmulator::default();
+        let mut at = SystemTime::now();
+        let mut elapsed = Duration::ZERO;
+        let mut records = Vec::new();
+        for i in 0..600 {
+            let dt = Duration::from_millis(80 + (i * 37) % 70);
+            engine.throttle = if i < 400 { 0.4 } else { 0.0 };
+            engine.step(dt);
+            engine.write_to(&mut ecu);
+            at += dt;
+            elapsed += dt;
+            let raw_data: HashMap<_, _> = ObdAddress::iter()
+                .map(|addr| (addr, ecu.get(addr)))
+                .collect();
+            records.push(
+                LogRecord::new(at, ecu.ecu_id, &raw_data)
+                    .resumed(i == 0)
+                    .elapsed(elapsed),
+            );
+        }
+        records
+    }
+
+    fn viewer() -> SuzukiSdlViewer {
+        let mut viewer = SuzukiSdlViewer::new(None);
+        viewer.engine_context = EngineContext::default();
+        viewer
+    }
+
+    /// What the dashboard computed while the drive was polled live.
+    fn live_totals(records: &[LogRecord]) -> (f64, f64) {
+        let mut viewer = viewer();
+        let start = Instant::now();
+        for record in records {
+            viewer.raw_data.extend(record.raw_data());
+            viewer.update_processed_data_at(start + record.elapsed);
+        }
+        let context = viewer.engine_context;
+        (context.cumulative_distance, context.total_fuel_used)
+    }
+
+    fn totals(viewer: &SuzukiSdlViewer) -> (f64, f64) {
+        let context = viewer.engine_context;
+        (context.cumulative_distance, context.total_fuel_used)
+    }
+
+    #[test]
+    fn test_totals_match_live_drive() {
+        let records = drive();
+        let live = live_totals(&records);
+        assert!(live.0 > 0.1 && live.1 > 0.01, "{live:?}");
+
+        // at 16x, ticking far slower than the records came in
+        let mut viewer = viewer();
+        let mut replay = Replay::new(records.clone());
+        for _ in 0..4 {
+            replay.faster();
+        }
+        let mut now = Instant::now();
+        for _ in 0..40 {
+            replay.tick(now, &mut viewer);
+            now += Duration::from_millis(250);
+        }
+        assert!(replay.is_finished());
+        assert_eq!(totals(&viewer), live);
+
+        // jumping around ends up in the same place
+        let mut viewer = viewer_with_totals();
+        let mut replay = Replay::new(records);
+        replay.seek(replay.duration(), &mut viewer);
+        replay.seek(Duration::from_secs(20), &mut viewer);
+        assert!(totals(&viewer).0 < live.0);
+        replay.step_back(&mut viewer);
+        replay.step_forward(&mut viewer);
+        replay.seek(replay.duration(), &mut viewer);
+        assert_eq!(totals(&viewer), live);
+    }
+
+    /// Totals loaded from the trip file have no place in a replay.
+    fn viewer_with_totals() -> SuzukiSdlViewer {
+        let mut viewer = viewer();
+        viewer.engine_context.cumulative_distance = 1234.0;
+        viewer.engine_context.total_fuel_used = 99.0;
+        viewer
+    }
+
+    #[test]
+    fn test_gap_before_reconnect_is_not_integrated() {
+        let records = drive();
+        let live = live_totals(&records);
+
+        let mut reconnected = records.clone();
+        for record in &mut reconnected[300..] {
+            record.at += Duration::from_secs(60);
+            record.elapsed += Duration::from_secs(60);
+        }
+        reconnected[300].resumed = true;
+        let mut viewer = viewer();
+        let mut replay = Replay::new(reconnected);
+        replay.seek(replay.duration(), &mut viewer);
+        let (distance, fuel) = totals(&viewer);
+        // only the one poll interval bridging the gap is missing
+        assert!(distance < live.0 && distance > live.0 * 0.99);
+        assert!(fuel < live.1 && fuel > live.1 * 0.99);
+    }
+
+    #[test]
+    fn test_wall_clock_step_is_not_integrated() {
+        let records = drive();
+        let live = live_totals(&records);
+
+        // NTP setting the clock an hour forward mid-drive, or a minute back
+        let forward = |at: SystemTime| at + Duration::from_secs(3600);
+        let back = |at: SystemTime| at - Duration::from_secs(60);
+        for step in [&forward as &dyn Fn(SystemTime) -> SystemTime, &back] {
+            let mut stepped = records.clone();
+            for record in &mut stepped[300..] {
+                record.at = step(record.at);
+            }
+            let mut viewer = viewer();
+            let mut replay = Replay::new(stepped.clone());
+            assert!(replay.duration() < Duration::from_secs(120));
+            replay.seek(replay.duration(), &mut viewer);
+            assert_eq!(totals(&viewer), live);
+            // the stepped clock is still what is shown
+            assert_eq!(replay.recorded_at(), stepped.last().map(|record| record.at));
+        }
+    }
+
+    #[test]
+    fn test_pause_speed_and_stepping() {
+        let records = drive();
+        let mut viewer = viewer();
+        let mut replay = Replay::new(records);
+        let now = Instant::now();
+        replay.tick(now, &mut viewer);
+        assert_eq!(replay.next, 1);
+
+        replay.faster();
+        replay.tick(now + Duration::from_secs(1), &mut viewer);
+        assert_eq!(replay.position(), Duration::from_secs(2));
+
+        replay.toggle_pause();
+        replay.tick(now + Duration::from_secs(5), &mut viewer);
+        assert_eq!(replay.position(), Duration::from_secs(2));
+
+        let next = replay.next;
+        replay.step_forward(&mut viewer);
+        assert_eq!(replay.next, next + 1);
+        replay.step_back(&mut viewer);
+        replay.step_back(&mut viewer);
+        assert_eq!(replay.next, next - 1);
+        assert!(replay.is_paused());
+
+        for _ in 0..10 {
+            replay.slower();
+        }
+        assert_eq!(replay.speed(), 0.25);
+        for _ in 0..10 {
+            replay.faster();
+        }
+        assert_eq!(replay.speed(), 16.0);
+    }
+}
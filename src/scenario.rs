@@ -0,0 +1,449 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    config::ConfigError,
+    sdl::{EngineContext, SuzukiSdlViewer},
+    simulator::EngineSimulator,
+    transport::SimulatedTransport,
+};
+
+/// Controls are applied and ramps moved along at least this often.
+const CONTROL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Timeline for the simulated engine, played back by `--simulate --scenario <file>` or in tests.
+///
+/// ```toml
+/// warm = true
+///
+/// [[steps]]
+/// name = "pull away"
+/// throttle = 0.5
+/// until_speed = 80
+/// duration = 60
+///
+/// [[steps]]
+/// name = "overheat"
+/// throttle = 0.0
+/// coolant_temp = 112
+/// duration = 30
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub ambient_temp: f32,
+
+    /// Start at operating temperature instead of from cold.
+    pub warm: bool,
+
+    pub steps: Vec<Step>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            ambient_temp: 25.0,
+            warm: false,
+            steps: Vec::new(),
+        }
+    }
+}
+
+/// One stretch of the timeline. Controls that are given are set at the start of the step and
+/// stay set after it, temperatures and battery voltage are ramped to over the step.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Step {
+    /// Shown while simulating, otherwise just for the reader.
+    pub name: String,
+
+    /// How long the step lasts in seconds, at most when `until_speed` is given.
+    pub duration: f64,
+
+    pub throttle: Option<f32>,
+    pub ac: Option<bool>,
+    pub lights: Option<bool>,
+
+    /// End the step early once road speed (km/h) gets here, from either side.
+    pub until_speed: Option<f32>,
+
+    /// Coolant temperature (C) to ramp to, the model takes over again after the step.
+    pub coolant_temp: Option<f32>,
+
+    /// Intake air temperature (C) to ramp to, the model takes over again after the step.
+    pub intake_air_temp: Option<f32>,
+
+    /// Battery voltage to ramp to, held after the step until another step sets it.
+    pub battery_voltage: Option<f32>,
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml(&contents)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(ConfigError::Parse)
+    }
+
+    /// Engine the scenario starts with.
+    pub fn engine(&self) -> EngineSimulator {
+        if self.warm {
+            EngineSimulator::warm(self.ambient_temp)
+        } else {
+            EngineSimulator::cold_start(self.ambient_temp)
+        }
+    }
+
+    /// Play the scenario through at a fixed `tick`, yielding the time since the start and what
+    /// the dashboard decodes at that point. Values go through the SDL protocol and the poll
+    /// scheduler the same way as with `--simulate`, but one tick per poll instead of on the wall
+    /// clock, so it's the same every run.
+    pub fn play(&self, tick: Duration) -> Playback {
+        let transport = SimulatedTransport::with_scenario(self.clone()).with_tick(tick);
+        Playback {
+            player: transport.scenario().unwrap(),
+            engine: transport.engine(),
+            viewer: SuzukiSdlViewer::with_transport(transport),
+            start: Instant::now(),
+            elapsed: Duration::ZERO,
+            tick,
+        }
+    }
+}
+
+/// Where playback is in a [`Scenario`], ramps start from the values at the start of a step.
+#[derive(Debug, Clone)]
+pub struct ScenarioPlayer {
+    scenario: Scenario,
+    step: usize,
+    step_elapsed: Duration,
+    ramp_start: Option<(f32, f32, f32)>,
+    speed_at_start: f32,
+}
+
+impl ScenarioPlayer {
+    pub fn new(scenario: Scenario) -> Self {
+        Self {
+            scenario,
+            step: 0,
+            step_elapsed: Duration::ZERO,
+            ramp_start: None,
+            speed_at_start: 0.0,
+        }
+    }
+
+    /// Step being played, `None` once the timeline ran out.
+    pub fn current_step(&self) -> Option<&Step> {
+        self.scenario.steps.get(self.step)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current_step().is_none()
+    }
+
+    /// Run `engine` for `dt`, applying the timeline as it goes. Once finished the engine keeps
+    /// running with the last controls.
+    pub fn advance(&mut self, dt: Duration, engine: &mut EngineSimulator) {
+        let mut remaining = dt;
+        while !remaining.is_zero() {
+            let chunk = remaining.min(CONTROL_INTERVAL);
+            self.apply(engine);
+            engine.step(chunk);
+            self.step_elapsed += chunk;
+            remaining -= chunk;
+            self.check_step_done(engine);
+        }
+    }
+
+    fn apply(&mut self, engine: &mut EngineSimulator) {
+        let Some(step) = self.scenario.steps.get(self.step) else {
+            return;
+        };
+        if self.ramp_start.is_none() {
+            if let Some(throttle) = step.throttle {
+                engine.throttle = throttle.clamp(0.0, 1.0);
+            }
+            if let Some(ac) = step.ac {
+                engine.ac = ac;
+            }
+            if let Some(lights) = step.lights {
+                engine.lights = lights;
+            }
+            self.speed_at_start = engine.speed();
+            self.ramp_start = Some((
+                engine.coolant_temp(),
+                engine.intake_air_temp(),
+                engine.battery_voltage(),
+            ));
+        }
+        let Some((coolant, intake, battery)) = self.ramp_start else {
+            return;
+        };
+
+        let progress = if step.duration > 0.0 {
+            (self.step_elapsed.as_secs_f64() / step.duration).min(1.0) as f32
+        } else {
+            1.0
+        };
+        let ramp = |from: f32, to: f32| from + (to - from) * progress;
+        if let Some(target) = step.coolant_temp {
+            engine.set_coolant_temp(ramp(coolant, target));
+        }
+        if let Some(target) = step.intake_air_temp {
+            engine.set_intake_air_temp(ramp(intake, target));
+        }
+        if let Some(target) = step.battery_voltage {
+            // what the battery would read without any drop, loads change along the way
+            let charging = engine.battery_voltage() + engine.battery_drop;
+            engine.battery_drop = charging - ramp(battery, target);
+        }
+    }
+
+    fn check_step_done(&mut self, engine: &EngineSimulator) {
+        let Some(step) = self.scenario.steps.get(self.step) else {
+            return;
+        };
+        let speed_reached = step.until_speed.is_some_and(|target| {
+            if self.speed_at_start < target {
+                engine.speed() >= target
+            } else {
+                engine.speed() <= target
+            }
+        });
+        if speed_reached || self.step_elapsed.as_secs_f64() >= step.duration {
+            self.step += 1;
+            self.step_elapsed = Duration::ZERO;
+            self.ramp_start = None;
+        }
+    }
+}
+
+/// Iterator returned by [`Scenario::play`].
+#[derive(Debug)]
+pub struct Playback {
+    player: Arc<Mutex<ScenarioPlayer>>,
+    engine: Arc<Mutex<EngineSimulator>>,
+    viewer: SuzukiSdlViewer,
+    /// Stands in for the start of the scenario, so polls and fuel go by scenario time.
+    start: Instant,
+    elapsed: Duration,
+    tick: Duration,
+}
+
+impl Playback {
+    /// Name of the step being played.
+    pub fn step_name(&self) -> Option<String> {
+        let player = self.player.lock().unwrap();
+        player.current_step().map(|step| step.name.clone())
+    }
+
+    /// Handle on the engine model being played.
+    pub fn engine(&self) -> Arc<Mutex<EngineSimulator>> {
+        self.engine.clone()
+    }
+}
+
+impl Iterator for Playback {
+    type Item = (Duration, EngineContext);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.player.lock().unwrap().is_finished() {
+            return None;
+        }
+        // every poll advances the engine by one tick
+        self.elapsed += self.tick;
+        let now = self.start + self.elapsed;
+        self.viewer.update_raw_data_at(now).ok()?;
+        self.viewer.update_processed_data_at(now);
+        Some((self.elapsed, self.viewer.engine_context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        style::Color,
+        widgets::{StatefulWidget, Widget},
+    };
+
+    use crate::{
+        alert::{CHARGING_VOLTAGE, LUGGING_LOAD, LUGGING_RPM, OVERHEAT_COOLANT_TEMP},
+        freeze_frame::{FreezeFrameStore, Trigger},
+        scenario::{Scenario, Step},
+        toggle_detector::ToggleDetector,
+        widgets::{
+            airflow::{AirflowBlock, AirflowBlockState},
+            electrical::ElectricalBlock,
+            temperature::{TemperatureBlock, TemperatureState},
+        },
+    };
+
+    const TICK: Duration = Duration::from_millis(100);
+
+    fn alerts() -> Scenario {
+        Scenario::from_toml(include_str!("../scenarios/alerts.toml")).unwrap()
+    }
+
+    fn has_fg(buf: &Buffer, color: Color) -> bool {
+        buf.content().iter().any(|cell| cell.fg == color)
+    }
+
+    fn has_bg(buf: &Buffer, color: Color) -> bool {
+        buf.content().iter().any(|cell| cell.bg == color)
+    }
+
+    #[test]
+    fn test_steps_until_speed_and_ramps() {
+        let scenario = Scenario {
+            warm: true,
+            steps: vec![
+                Step {
+                    throttle: Some(0.5),
+                    until_speed: Some(60.0),
+                    duration: 60.0,
+                    ..Step::default()
+                },
+                Step {
+                    throttle: Some(0.0),
+                    coolant_temp: Some(105.0),
+                    battery_voltage: Some(12.0),
+                    duration: 10.0,
+                    ..Step::default()
+                },
+            ],
+            ..Scenario::default()
+        };
+        let mut playback = scenario.play(TICK);
+        let (reached_at, ctx) = playback
+            .by_ref()
+            .find(|(_, ctx)| ctx.vehicle_speed >= 60)
+            .unwrap();
+        assert!(reached_at < Duration::from_secs(30));
+        assert_eq!(ctx.vehicle_speed, 60);
+
+        let (ended_at, ctx) = playback.last().unwrap();
+        // speed decodes as 60 a little before it is really 60.0
+        let second_step = (ended_at - reached_at).as_secs_f32();
+        assert!((10.0..10.5).contains(&second_step), "{second_step}");
+        // coolant and battery are in the slow group, read up to a second before the ramps end
+        assert!(
+            (103..=105).contains(&ctx.coolant_temp),
+            "{}",
+            ctx.coolant_temp
+        );
+        assert!(
+            (ctx.battery_voltage - 12.0).abs() < 0.2,
+            "{}",
+            ctx.battery_voltage
+        );
+    }
+
+    #[test]
+    fn test_alert_paths() {
+        let start = Instant::now();
+        let area = Rect::new(0, 0, 40, 8);
+        let mut temperature_state = TemperatureState::new();
+        let mut airflow_state = AirflowBlockState::new();
+        let mut trip_reset = ToggleDetector::new();
+        let mut freeze_frames = FreezeFrameStore::new();
+        let recorded_from = SystemTime::now();
+
+        let mut ect_blink = (false, false);
+        let mut lugging_blink = (false, false);
+        let mut battery_red = false;
+        let mut fuel_cut = false;
+        let mut trip_resets = Vec::new();
+        for (at, ctx) in alerts().play(TICK) {
+            let now = start + at;
+
+            let mut buf = Buffer::empty(area);
+            TemperatureBlock::new(&ctx)
+                .at(now)
+                .render(area, &mut buf, &mut temperature_state);
+            if ctx.coolant_temp >= OVERHEAT_COOLANT_TEMP {
+                let red = has_fg(&buf, Color::Red);
+                ect_blink = (ect_blink.0 || red, ect_blink.1 || !red);
+            } else {
+                assert!(!has_fg(&buf, Color::Red), "ECT {}", ctx.coolant_temp);
+            }
+
+            let mut buf = Buffer::empty(area);
+            AirflowBlock::new(&ctx)
+                .at(now)
+                .render(area, &mut buf, &mut airflow_state);
+            if ctx.engine_speed < LUGGING_RPM && ctx.calculated_load >= LUGGING_LOAD {
+                let red = has_fg(&buf, Color::Red);
+                lugging_blink = (lugging_blink.0 || red, lugging_blink.1 || !red);
+            }
+
+            let mut buf = Buffer::empty(area);
+            ElectricalBlock::new(&ctx).render(area, &mut buf);
+            let out_of_range = !CHARGING_VOLTAGE.contains(&ctx.battery_voltage);
+            assert_eq!(
+                has_bg(&buf, Color::Red),
+                out_of_range,
+                "{}",
+                ctx.battery_voltage
+            );
+            battery_red |= out_of_range;
+
+            freeze_frames
+                .update(recorded_from + at, None, &ctx, &HashMap::new())
+                .unwrap();
+
+            fuel_cut |= ctx.fuel_cut;
+            if trip_reset.update_at(ctx.electric_load, now) {
+                trip_resets.push(at);
+            }
+        }
+
+        assert_eq!(ect_blink, (true, true));
+        assert_eq!(lugging_blink, (true, true));
+        assert!(battery_red);
+        assert!(fuel_cut);
+        assert_eq!(trip_resets.len(), 1);
+
+        // every alert leaves a freeze frame
+        for trigger in [Trigger::Overheat, Trigger::Battery, Trigger::Lugging] {
+            assert!(
+                freeze_frames.frames().any(|frame| frame.trigger == trigger),
+                "{trigger}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_slow_toggling_does_not_reset_trip() {
+        let step = |lights| Step {
+            lights: Some(lights),
+            duration: 3.0,
+            ..Step::default()
+        };
+        let scenario = Scenario {
+            warm: true,
+            steps: [true, false].repeat(4).into_iter().map(step).collect(),
+            ..Scenario::default()
+        };
+        let start = Instant::now();
+        let mut trip_reset = ToggleDetector::new();
+        let resets = scenario
+            .play(TICK)
+            .filter(|(at, ctx)| trip_reset.update_at(ctx.electric_load, start + *at))
+            .count();
+        assert_eq!(resets, 0);
+    }
+}
@@ -1,13 +1,27 @@
-use serialport::TTYPort;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    io::{Read, Write},
+    io::{self, Read, Write},
+    path::Path,
     time::{Duration, Instant},
 };
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, FromRepr};
 
-use crate::strings::{DISTANCE_FUEL_FILE_PATH, VAG_KKL_PORT};
+use crate::{
+    actuator::{ActuatorTest, actuation_allowed},
+    config::Config,
+    connection::{Backoff, ConnectionState, Connector, MAX_POLL_FAILURES},
+    decoder::SdlFrameDecoder,
+    dtc::{CURRENT_FAULT_ADDRESSES, DiagnosticTroubleCodes, HISTORY_FAULT_ADDRESSES},
+    ecu::{self, G13BB, KnownEcu, ParameterProfile},
+    error::SdlError,
+    export::Channel,
+    polling::PollScheduler,
+    stats::LinkStats,
+    transport::Transport,
+    vehicle::VehicleProfile,
+};
 
 #[derive(Debug)]
 pub struct ScanToolParameterValue {
@@ -15,14 +29,8 @@ pub struct ScanToolParameterValue {
     pub unit: Option<String>,
 }
 
-/// Flow rate for a single injector in (cc/min). Spec for inj. is about 38-48 (cc/15s) i.e 152-192
-/// (cc/min).
-/// UPDATE: The real-world reading was too high. 68% overestimation. Correction applied.
-/// UPDATE 2: Correction applied (90cc/min) gave ~4% overestimation. Applying further correction.
-const INJECTOR_FLOW_RATE: f64 = 87.8;
-
 /// Struct that contains all processed engine parameters with their representative values.
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct EngineContext {
     /// Intended idle by ECU. Affected by A/C idle-up and ECT.
     pub desired_idle: u16,
@@ -63,7 +71,8 @@ pub struct EngineContext {
     /// just what the ECU is being supplied through the dedicated BATT+ wire.
     pub battery_voltage: f32,
 
-    /// Ignition advance as being commanded by the ECU. Fixed spark is 5 BTDC for verification.
+    /// Ignition advance as being commanded by the ECU. Fixed spark is 5 BTDC for verification, see
+    /// [`ActuatorTest::FixedSpark`].
     pub ignition_advance: i8,
 
     /// Switch to tell if throttle is fully closed. Used to engage idle strategy, fuel cut etc.
@@ -114,10 +123,20 @@ pub struct EngineContext {
     /// Instantaneous fuel flow rate in (L/hr).
     pub fuel_flow_rate: f64,
 
-    /// Time when ECU was last polled for data.
+    /// Time when ECU was last polled for data. Not saved, it means nothing to another run.
+    #[serde(skip)]
     pub last_poll: Option<Instant>,
 }
 
+impl EngineContext {
+    /// Zero the trip meter, i.e. distance and fuel totals.
+    pub fn reset_trip(&mut self) {
+        self.cumulative_distance = 0.0;
+        self.cumulative_fuel = 0.0;
+        self.total_fuel_used = 0.0;
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, Hash, FromRepr, Display)]
 pub enum ObdAddress {
     FaultCodes1 = 0x00,
@@ -145,6 +164,21 @@ pub enum ObdAddress {
     FaultCodes6 = 0x21,
 }
 
+impl ObdAddress {
+    /// Whether this address holds fault code bits rather than a live parameter.
+    pub fn is_fault_code(&self) -> bool {
+        matches!(
+            self,
+            ObdAddress::FaultCodes1
+                | ObdAddress::FaultCodes2
+                | ObdAddress::FaultCodes3
+                | ObdAddress::FaultCodes4
+                | ObdAddress::FaultCodes5
+                | ObdAddress::FaultCodes6
+        )
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, Hash, Display)]
 pub enum ScanToolParameter {
     DesiredIdle,
@@ -170,18 +204,87 @@ pub enum ScanToolParameter {
     FuelConsumption,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, FromRepr)]
-enum SdlHeader {
+impl ScanToolParameter {
+    /// Short name for labels.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScanToolParameter::DesiredIdle => "Desired idle",
+            ScanToolParameter::EngineSpeed => "Engine speed",
+            ScanToolParameter::IacFlowDutyCycle => "ISC flow duty",
+            ScanToolParameter::AbsoluteThrottlePosition => "Abs. throttle",
+            ScanToolParameter::ThrottleAngle => "Throttle angle",
+            ScanToolParameter::InjPulseWidthCyl1 => "Inj. pulse width",
+            ScanToolParameter::CoolantTemp => "ECT",
+            ScanToolParameter::VehicleSpeed => "Vehicle speed",
+            ScanToolParameter::IntakeAirTemp => "IAT",
+            ScanToolParameter::Map => "MAP",
+            ScanToolParameter::BarometricPressure => "BARO",
+            ScanToolParameter::BatteryVoltage => "Battery",
+            ScanToolParameter::IgnitionAdvance => "Ignition advance",
+            ScanToolParameter::ClosedThrottlePos => "CTP",
+            ScanToolParameter::ElectricLoad => "EL",
+            ScanToolParameter::FuelCut => "Fuel cut",
+            ScanToolParameter::AcSwitch => "AC",
+            ScanToolParameter::PspSwitch => "PSP",
+            ScanToolParameter::RadiatorFan => "RAD",
+            ScanToolParameter::CalculatedLoad => "Calc. load",
+            ScanToolParameter::FuelConsumption => "Fuel flow",
+        }
+    }
+
+    /// Export channel holding this parameter's unit and value. Fuel consumption is shown as the
+    /// instantaneous flow rate, which unlike L/100km means something at a standstill too.
+    pub fn channel(&self) -> Channel {
+        match self {
+            ScanToolParameter::DesiredIdle => Channel::DesiredIdle,
+            ScanToolParameter::EngineSpeed => Channel::EngineSpeed,
+            ScanToolParameter::IacFlowDutyCycle => Channel::IscFlowDuty,
+            ScanToolParameter::AbsoluteThrottlePosition => Channel::AbsoluteThrottlePosition,
+            ScanToolParameter::ThrottleAngle => Channel::ThrottleAngle,
+            ScanToolParameter::InjPulseWidthCyl1 => Channel::InjectorPulseWidthCyl1,
+            ScanToolParameter::CoolantTemp => Channel::CoolantTemp,
+            ScanToolParameter::VehicleSpeed => Channel::VehicleSpeed,
+            ScanToolParameter::IntakeAirTemp => Channel::IntakeAirTemperature,
+            ScanToolParameter::Map => Channel::ManifoldAbsolutePressure,
+            ScanToolParameter::BarometricPressure => Channel::BarometricPressure,
+            ScanToolParameter::BatteryVoltage => Channel::BatteryVoltage,
+            ScanToolParameter::IgnitionAdvance => Channel::IgnitionAdvance,
+            ScanToolParameter::ClosedThrottlePos => Channel::ClosedThrottlePosition,
+            ScanToolParameter::ElectricLoad => Channel::ElectricLoad,
+            ScanToolParameter::FuelCut => Channel::FuelCut,
+            ScanToolParameter::AcSwitch => Channel::AcSwitch,
+            ScanToolParameter::PspSwitch => Channel::PspSwitch,
+            ScanToolParameter::RadiatorFan => Channel::RadiatorFan,
+            ScanToolParameter::CalculatedLoad => Channel::CalculatedLoad,
+            ScanToolParameter::FuelConsumption => Channel::FuelFlowRate,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        self.channel().unit()
+    }
+
+    /// Processed value in `context`, see [`Channel::value`].
+    pub fn value(&self, context: &EngineContext) -> f64 {
+        self.channel().value(context)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromRepr, Display)]
+pub enum SdlHeader {
     Id = 0x10,
     Data = 0x13,
+    /// Clear fault code memory. ECU acknowledges with an empty frame of the same header.
+    ClearCodes = 0x14,
     Actuate = 0x15,
 }
 
+/// A single SDL frame: header, length, data and checksum.
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct SdlMessage {
-    header: SdlHeader,
+pub struct SdlMessage {
+    pub(crate) header: SdlHeader,
     length: u8,
-    data: Option<Vec<u8>>,
+    pub(crate) data: Option<Vec<u8>>,
     checksum: u8,
 }
 
@@ -205,7 +308,15 @@ impl SdlMessage {
         }
     }
 
-    fn to_bytes(&self) -> Vec<u8> {
+    pub fn header(&self) -> SdlHeader {
+        self.header
+    }
+
+    pub fn data(&self) -> &[u8] {
+        self.data.as_deref().unwrap_or_default()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes: Vec<u8> = Vec::new();
         bytes.push(self.header as u8);
         bytes.push(self.length);
@@ -219,15 +330,24 @@ impl SdlMessage {
 }
 
 impl TryFrom<&[u8]> for SdlMessage {
-    type Error = String;
+    type Error = SdlError;
 
     fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
         if value.len() < 3 {
-            return Err("length of bytes less than min. of 3".to_string());
+            return Err(SdlError::TruncatedFrame {
+                expected: 3,
+                actual: value.len(),
+            });
         }
         let header = value[0];
-        let header_enum = SdlHeader::from_repr(header.into()).unwrap();
+        let header_enum = SdlHeader::from_repr(header.into()).ok_or(SdlError::BadHeader(header))?;
         let length = value[1];
+        if length < 3 || length as usize > value.len() {
+            return Err(SdlError::TruncatedFrame {
+                expected: (length as usize).max(3),
+                actual: value.len(),
+            });
+        }
         let data = &value[2..length as usize - 1];
         let d = if !data.is_empty() {
             data.to_vec()
@@ -235,6 +355,13 @@ impl TryFrom<&[u8]> for SdlMessage {
             vec![]
         };
         let checksum = value[length as usize - 1];
+        let expected = Self::generate_checksum(&value[..length as usize - 1]);
+        if checksum != expected {
+            return Err(SdlError::BadChecksum {
+                expected,
+                actual: checksum,
+            });
+        }
         Ok(Self {
             header: header_enum,
             length,
@@ -246,130 +373,379 @@ impl TryFrom<&[u8]> for SdlMessage {
 
 #[derive(Debug)]
 pub struct SuzukiSdlViewer {
-    port: Option<TTYPort>,
-    pub ecu_id: Option<String>,
+    connector: Option<Box<dyn Connector>>,
+    port: Option<Box<dyn Transport>>,
+    pub connection_state: ConnectionState,
+    /// When raw data was last refreshed from the ECU, to tell how stale the values on screen are.
+    pub last_data: Option<Instant>,
+    poll_failures: u32,
+    backoff: Backoff,
+    /// Minimum gap between the end of one request and the start of the next.
+    pub request_delay: Duration,
+    last_request: Option<Instant>,
+    poll_scheduler: PollScheduler,
+    /// ID the ECU answered with, see [`ecu::format_ecu_id`] for showing it.
+    pub ecu_id: Option<[u8; 2]>,
+    /// Entry in the ECU database matching [`Self::ecu_id`], `None` if the ECU is unknown.
+    pub ecu: Option<&'static KnownEcu>,
+    /// ECUs recognised on top of [`ecu::KNOWN_ECUS`], e.g. the simulator's.
+    pub extra_ecus: &'static [KnownEcu],
     pub raw_data: HashMap<ObdAddress, u8>,
     pub engine_context: EngineContext,
+    pub vehicle: VehicleProfile,
+    pub fault_codes: DiagnosticTroubleCodes,
+    pub active_actuator_test: Option<ActuatorTest>,
+    pub link_stats: LinkStats,
+    decoder: SdlFrameDecoder,
 }
 
 impl Default for SuzukiSdlViewer {
     fn default() -> Self {
-        let vag_kkl = serialport::new(VAG_KKL_PORT, 7812)
-            .timeout(Duration::from_secs(1))
-            .open_native();
+        Self::new(Some(Config::default().connector()))
+    }
+}
+
+impl SuzukiSdlViewer {
+    /// Construct a viewer that opens its port through `connector`, again every time the port
+    /// goes away. Nothing is opened until the first [`Self::poll_ecu`].
+    pub fn new(connector: Option<Box<dyn Connector>>) -> Self {
         let mut raw_data: HashMap<ObdAddress, u8> = HashMap::new();
 
         for obd_address in ObdAddress::iter() {
             raw_data.insert(obd_address, 0);
         }
 
-        // load up cumulative data from file if valid.
-        let mut engine_context = EngineContext::default();
-        let distance_fuel =
-            std::fs::read_to_string(DISTANCE_FUEL_FILE_PATH).unwrap_or("0,0,0".to_string());
-        let split: Vec<&str> = distance_fuel.trim().split(',').collect();
-
-        engine_context.cumulative_distance =
-            split.get(0).and_then(|v| v.parse().ok()).unwrap_or(0.0);
-        engine_context.cumulative_fuel = split.get(1).and_then(|v| v.parse().ok()).unwrap_or(0.0);
-        engine_context.total_fuel_used = split.get(2).and_then(|v| v.parse().ok()).unwrap_or(0.0);
-
         Self {
-            port: vag_kkl.ok(),
+            connector,
+            port: None,
+            connection_state: ConnectionState::Disconnected,
+            last_data: None,
+            poll_failures: 0,
+            backoff: Backoff::default(),
+            request_delay: Duration::ZERO,
+            last_request: None,
+            poll_scheduler: PollScheduler::default(),
             ecu_id: None,
+            ecu: None,
+            extra_ecus: &[],
             raw_data,
-            engine_context,
+            engine_context: EngineContext::default(),
+            vehicle: VehicleProfile::default(),
+            fault_codes: DiagnosticTroubleCodes::default(),
+            active_actuator_test: None,
+            link_stats: LinkStats::new(),
+            decoder: SdlFrameDecoder::new(),
         }
     }
-}
 
-impl SuzukiSdlViewer {
-    /// Query ECU ID.
-    fn get_ecu_id(&mut self) -> String {
-        let header = SdlHeader::Id;
-        let data = None;
-        let sdl_message = SdlMessage::new(header, data);
-        let written = self.port.as_mut().unwrap().write(&sdl_message.to_bytes());
-        let bytes_written = written.unwrap();
-        let mut echo_buf = vec![0; bytes_written];
-        let mut response_buf = vec![0; 5];
-        self.port
-            .as_mut()
-            .unwrap()
-            .read_exact(&mut echo_buf)
-            .unwrap(); // echo
-        self.port
-            .as_mut()
-            .unwrap()
-            .read_exact(&mut response_buf)
-            .unwrap();
-        let ecu_id = &response_buf[2..=3];
-        format!("{:#02x}{:#02x}", ecu_id[0], ecu_id[1])
+    /// Pick up the cumulative distance and fuel a previous session saved to `path`. Anything
+    /// missing or unreadable starts from zero. Only the dashboard does this, a viewer is also
+    /// built for every port probed and every log exported.
+    pub fn load_trip(&mut self, path: impl AsRef<Path>) {
+        // load up cumulative data from file if valid.
+        let distance_fuel = std::fs::read_to_string(path).unwrap_or("0,0,0".to_string());
+        let split: Vec<&str> = distance_fuel.trim().split(',').collect();
+
+        self.engine_context.cumulative_distance =
+            split.first().and_then(|v| v.parse().ok()).unwrap_or(0.0);
+        self.engine_context.cumulative_fuel =
+            split.get(1).and_then(|v| v.parse().ok()).unwrap_or(0.0);
+        self.engine_context.total_fuel_used =
+            split.get(2).and_then(|v| v.parse().ok()).unwrap_or(0.0);
     }
 
-    /// Query obd addresses and update raw data.
-    pub fn update_raw_data(&mut self, should_simulate: bool) {
-        if should_simulate {
-            for (_, v) in self.raw_data.iter_mut() {
-                *v = v.wrapping_add(1);
+    pub fn with_connector(connector: impl Connector + 'static) -> Self {
+        Self::new(Some(Box::new(connector)))
+    }
+
+    /// Construct a viewer on top of an already open [`Transport`]. Can't be reopened if it goes
+    /// away.
+    pub fn with_transport(transport: impl Transport + 'static) -> Self {
+        let mut viewer = Self::new(None);
+        viewer.port = Some(Box::new(transport));
+        viewer.connection_state = ConnectionState::Probing;
+        viewer
+    }
+
+    /// One step of the connection state machine. Opens the port when disconnected, probes with
+    /// an ID request until the ECU answers and polls raw data once streaming. Retries back off
+    /// while the ECU stays silent. Returns whether raw data was refreshed.
+    pub fn poll_ecu(&mut self) -> Result<bool, SdlError> {
+        let now = Instant::now();
+        if self.connection_state.is_streaming() {
+            self.update_raw_data()?;
+            return Ok(true);
+        }
+        if !self.backoff.is_due(now) {
+            return Ok(false);
+        }
+        if self.port.is_none() {
+            let connector = self
+                .connector
+                .as_mut()
+                .ok_or(SdlError::PortGone(io::ErrorKind::NotConnected))?;
+            match connector.open() {
+                Ok(port) => {
+                    self.port = Some(port);
+                    self.connection_state = ConnectionState::Probing;
+                }
+                Err(err) => {
+                    let err = SdlError::PortGone(err.kind());
+                    self.link_stats.record_error(&err);
+                    self.backoff.failed(now);
+                    return Err(err);
+                }
             }
+        }
+        self.connect()?;
+        Ok(false)
+    }
+
+    /// Move the connection state machine along after a failed request.
+    fn request_failed(&mut self, err: &SdlError) {
+        let now = Instant::now();
+        if let SdlError::PortGone(_) = err {
+            self.port = None;
+            self.connection_state = ConnectionState::Disconnected;
+            self.backoff.failed(now);
             return;
         }
-        let header = SdlHeader::Data;
-        let data = Some(
-            ObdAddress::iter()
-                .filter(|v| {
-                    !matches!(
-                        v,
-                        ObdAddress::FaultCodes1
-                            | ObdAddress::FaultCodes2
-                            | ObdAddress::FaultCodes3
-                            | ObdAddress::FaultCodes4
-                            | ObdAddress::FaultCodes5
-                            | ObdAddress::FaultCodes6
-                    )
-                })
-                .map(|v| v as u8)
-                .collect(),
-        );
-        let sdl_message = SdlMessage::new(header, data);
-        let written = self.port.as_mut().unwrap().write(&sdl_message.to_bytes());
-        let bytes_written = written.unwrap();
-        let mut echo_buf: Vec<u8> = vec![0; bytes_written];
-        let mut response_buf: Vec<u8> = vec![0; bytes_written];
-        let _ = self.port.as_mut().unwrap().read_exact(&mut echo_buf); // echo
-        let _ = self
+        match self.connection_state {
+            ConnectionState::Streaming => {
+                self.poll_failures += 1;
+                if self.poll_failures >= MAX_POLL_FAILURES {
+                    self.connection_state = ConnectionState::Lost;
+                    self.active_actuator_test = None;
+                    self.backoff.failed(now);
+                }
+            }
+            _ => self.backoff.failed(now),
+        }
+    }
+
+    /// Send a request and read back its echo followed by the ECU response, whatever its length.
+    /// Corrupted frames are counted in [`Self::link_stats`] and never reach raw data.
+    fn transact(&mut self, request: &SdlMessage) -> Result<SdlMessage, SdlError> {
+        let port = self
             .port
             .as_mut()
-            .unwrap()
-            .read_exact(response_buf.as_mut_slice());
-        let request = sdl_message;
-        let response = SdlMessage::try_from(&response_buf[..]).unwrap();
-
-        if let Some(addrs) = request.data
-            && let Some(values) = response.data
-        {
-            for (addr, value) in addrs.iter().zip(values.iter()) {
-                if let Some(obd_addr) = ObdAddress::from_repr(*addr as usize) {
-                    self.raw_data.insert(obd_addr, *value);
+            .ok_or(SdlError::PortGone(io::ErrorKind::NotConnected))?;
+        if let Some(last_request) = self.last_request {
+            std::thread::sleep(self.request_delay.saturating_sub(last_request.elapsed()));
+        }
+        self.decoder.clear();
+        let started = Instant::now();
+        let result = Self::exchange(port, &mut self.decoder, request);
+        let finished = Instant::now();
+        self.last_request = Some(finished);
+        match &result {
+            Ok(_) => {
+                self.poll_failures = 0;
+                self.link_stats
+                    .record_response(finished, finished.duration_since(started));
+            }
+            Err(err) => {
+                self.link_stats.record_error(err);
+                // don't let half a response leak into the next request
+                let _ = port.clear_input();
+                self.decoder.clear();
+                self.request_failed(err);
+            }
+        }
+        result
+    }
+
+    fn exchange(
+        port: &mut Box<dyn Transport>,
+        decoder: &mut SdlFrameDecoder,
+        request: &SdlMessage,
+    ) -> Result<SdlMessage, SdlError> {
+        let request_bytes = request.to_bytes();
+        port.write_all(&request_bytes)?;
+
+        // K-line is a single wire, the first frame we read back is our own request.
+        let echo = Self::read_frame(port, decoder)?;
+        if echo != *request {
+            return Err(SdlError::EchoMismatch {
+                sent: request_bytes,
+                received: echo.to_bytes(),
+            });
+        }
+
+        let response = Self::read_frame(port, decoder)?;
+        if response.header != request.header {
+            return Err(SdlError::UnexpectedResponse(response.to_bytes()));
+        }
+        // one value per requested address, anything else can't be lined up with the addresses
+        if request.header == SdlHeader::Data && response.data().len() != request.data().len() {
+            return Err(SdlError::UnexpectedResponse(response.to_bytes()));
+        }
+        Ok(response)
+    }
+
+    /// Read from the port until the decoder yields a frame.
+    fn read_frame(
+        port: &mut Box<dyn Transport>,
+        decoder: &mut SdlFrameDecoder,
+    ) -> Result<SdlMessage, SdlError> {
+        let mut chunk = [0u8; 64];
+        loop {
+            if let Some(frame) = decoder.next_frame() {
+                return Ok(frame);
+            }
+            match port.read(&mut chunk) {
+                Ok(0) => return Err(SdlError::PortGone(io::ErrorKind::UnexpectedEof)),
+                Ok(len) => decoder.push(&chunk[..len]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    // line went quiet, report why the bytes we did get were not a frame
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) {
+                        if let Some(rejection) = decoder.take_rejection() {
+                            return Err(rejection);
+                        }
+                        if !decoder.pending().is_empty() {
+                            return Err(SdlError::TruncatedFrame {
+                                expected: decoder.pending().get(1).map_or(3, |len| *len as usize),
+                                actual: decoder.pending().len(),
+                            });
+                        }
+                    }
+                    return Err(err.into());
                 }
             }
         }
     }
 
+    /// Query ECU ID.
+    fn get_ecu_id(&mut self) -> Result<[u8; 2], SdlError> {
+        let sdl_message = SdlMessage::new(SdlHeader::Id, None);
+        let response = self.transact(&sdl_message)?;
+        match response.data.as_deref() {
+            Some([high, low]) => Ok([*high, *low]),
+            _ => Err(SdlError::UnexpectedResponse(response.to_bytes())),
+        }
+    }
+
+    /// Query the given obd addresses in a single Data request and store the values in raw data.
+    fn query(&mut self, addrs: &[ObdAddress]) -> Result<(), SdlError> {
+        let raw_addrs: Vec<u8> = addrs.iter().map(|v| *v as u8).collect();
+        let values = self.query_raw(&raw_addrs)?;
+        for (addr, value) in addrs.iter().zip(values) {
+            self.raw_data.insert(*addr, value);
+        }
+        Ok(())
+    }
+
+    /// Query any addresses in a single Data request, known to [`ObdAddress`] or not. Values are
+    /// returned in request order and not stored anywhere.
+    pub fn query_raw(&mut self, addrs: &[u8]) -> Result<Vec<u8>, SdlError> {
+        let request = SdlMessage::new(SdlHeader::Data, Some(addrs.to_vec()));
+        let response = self.transact(&request)?;
+        Ok(response.data().to_vec())
+    }
+
+    /// Clear fault codes stored in ECU memory and re-read them to refresh [`Self::fault_codes`].
+    pub fn clear_fault_codes(&mut self) -> Result<(), SdlError> {
+        let request = SdlMessage::new(SdlHeader::ClearCodes, None);
+        self.transact(&request)?;
+        self.read_fault_codes()?;
+        Ok(())
+    }
+
+    /// Switch an active test on or off. ECU acknowledges by sending the request back.
+    fn actuate(&mut self, test: ActuatorTest, on: bool) -> Result<(), SdlError> {
+        let request = SdlMessage::new(SdlHeader::Actuate, Some(vec![test.command(), on as u8]));
+        let response = self.transact(&request)?;
+        if response != request {
+            return Err(SdlError::UnexpectedResponse(response.to_bytes()));
+        }
+        Ok(())
+    }
+
+    /// Start an active test, stopping any other running test first. Refused while the car is
+    /// being driven.
+    pub fn start_actuator_test(&mut self, test: ActuatorTest) -> Result<(), SdlError> {
+        if !actuation_allowed(&self.engine_context) {
+            return Err(SdlError::Refused("vehicle moving or engine above idle"));
+        }
+        self.stop_actuator_test()?;
+        self.actuate(test, true)?;
+        self.active_actuator_test = Some(test);
+        Ok(())
+    }
+
+    /// Stop the running active test, if any. It stays active until the ECU acknowledges, so a
+    /// stop that went unanswered is tried again.
+    pub fn stop_actuator_test(&mut self) -> Result<(), SdlError> {
+        if let Some(test) = self.active_actuator_test {
+            self.actuate(test, false)?;
+            self.active_actuator_test = None;
+        }
+        Ok(())
+    }
+
+    /// Stop the running active test once the car starts moving, retried every poll until the
+    /// ECU acknowledges.
+    pub fn enforce_actuator_safety(&mut self) -> Result<(), SdlError> {
+        if self.active_actuator_test.is_some() && !actuation_allowed(&self.engine_context) {
+            self.stop_actuator_test()?;
+        }
+        Ok(())
+    }
+
+    /// Query obd addresses and update raw data. Fast changing addresses are queried every time,
+    /// slow ones only every so often, see [`PollScheduler`].
+    pub fn update_raw_data(&mut self) -> Result<(), SdlError> {
+        self.update_raw_data_at(Instant::now())
+    }
+
+    /// [`Self::update_raw_data`] with the time of the poll given, for scenario playback.
+    pub fn update_raw_data_at(&mut self, now: Instant) -> Result<(), SdlError> {
+        let profile = self.parameter_profile();
+        let mut addrs = self.poll_scheduler.next_addresses(now);
+        addrs.retain(|addr| profile.supports(*addr));
+        self.query(&addrs)?;
+        self.poll_scheduler.polled(&addrs, now);
+        self.link_stats.record_poll(Instant::now());
+        self.last_data = Some(now);
+        Ok(())
+    }
+
+    /// Query fault code bytes from ECU and decode them into current and history codes.
+    pub fn read_fault_codes(&mut self) -> Result<&DiagnosticTroubleCodes, SdlError> {
+        let addrs = [CURRENT_FAULT_ADDRESSES, HISTORY_FAULT_ADDRESSES].concat();
+        self.query(&addrs)?;
+        let current = CURRENT_FAULT_ADDRESSES.map(|addr| self.raw_data[&addr]);
+        let history = HISTORY_FAULT_ADDRESSES.map(|addr| self.raw_data[&addr]);
+        self.fault_codes = DiagnosticTroubleCodes::from_raw(current, history);
+        Ok(&self.fault_codes)
+    }
+
     /// Update scan tool data from raw values.
     pub fn update_processed_data(&mut self) {
+        self.update_processed_data_at(Instant::now());
+    }
+
+    /// Update scan tool data from raw values polled at `now`, fuel and distance are integrated
+    /// over the time since the previous poll.
+    pub fn update_processed_data_at(&mut self, now: Instant) {
+        let profile = self.parameter_profile();
         for scan_tool_parameter in ScanToolParameter::iter() {
             match scan_tool_parameter {
                 ScanToolParameter::DesiredIdle => {
                     let raw_value = self.raw_data.get(&ObdAddress::TargetIdle).unwrap();
-                    self.engine_context.desired_idle = Self::calculate_desired_idle(*raw_value);
+                    self.engine_context.desired_idle =
+                        Self::calculate_desired_idle(profile, *raw_value);
                 }
                 ScanToolParameter::EngineSpeed => {
                     let low_byte = self.raw_data.get(&ObdAddress::RpmLow).unwrap();
                     let high_byte = self.raw_data.get(&ObdAddress::RpmHigh).unwrap();
                     self.engine_context.engine_speed =
-                        Self::calculate_rpm_high(*high_byte) + Self::calculate_rpm_low(*low_byte);
+                        Self::calculate_rpm_high(profile, *high_byte)
+                            + Self::calculate_rpm_low(profile, *low_byte);
                 }
                 ScanToolParameter::IacFlowDutyCycle => {
                     let raw_value = self.raw_data.get(&ObdAddress::IscFlowDuty).unwrap();
@@ -377,12 +753,13 @@ impl SuzukiSdlViewer {
                 }
                 ScanToolParameter::ThrottleAngle => {
                     let raw_value = self.raw_data.get(&ObdAddress::TpsAngle).unwrap();
-                    self.engine_context.throttle_angle = Self::calculate_tps_angle(*raw_value);
+                    self.engine_context.throttle_angle =
+                        Self::calculate_tps_angle(profile, *raw_value);
                 }
                 ScanToolParameter::BatteryVoltage => {
                     let raw_value = self.raw_data.get(&ObdAddress::BatteryVoltage).unwrap();
                     self.engine_context.battery_voltage =
-                        Self::calculate_battery_voltage(*raw_value);
+                        Self::calculate_battery_voltage(profile, *raw_value);
                 }
                 ScanToolParameter::CoolantTemp | ScanToolParameter::IntakeAirTemp => {
                     let raw_value = if scan_tool_parameter == ScanToolParameter::CoolantTemp {
@@ -394,7 +771,7 @@ impl SuzukiSdlViewer {
                             .get(&ObdAddress::IntakeAirTemperature)
                             .unwrap()
                     };
-                    let processed_value = Self::calculate_temps(*raw_value);
+                    let processed_value = Self::calculate_temps(profile, *raw_value);
                     if scan_tool_parameter == ScanToolParameter::CoolantTemp {
                         self.engine_context.coolant_temp = processed_value;
                     } else {
@@ -411,8 +788,8 @@ impl SuzukiSdlViewer {
                         .get(&ObdAddress::InjectorPulseWidthHigh)
                         .unwrap();
                     self.engine_context.injector_pulse_width_cyl_1 =
-                        Self::calculate_inj_pw_high(*high_byte)
-                            + Self::calculate_inj_pw_low(*low_byte);
+                        Self::calculate_inj_pw_high(profile, *high_byte)
+                            + Self::calculate_inj_pw_low(profile, *low_byte);
                 }
                 ScanToolParameter::Map | ScanToolParameter::BarometricPressure => {
                     let raw_value = if scan_tool_parameter == ScanToolParameter::Map {
@@ -422,7 +799,7 @@ impl SuzukiSdlViewer {
                     } else {
                         self.raw_data.get(&ObdAddress::BarometricPressure).unwrap()
                     };
-                    let processed_value = Self::calculate_pressure(*raw_value);
+                    let processed_value = Self::calculate_pressure(profile, *raw_value);
                     if scan_tool_parameter == ScanToolParameter::Map {
                         self.engine_context.manifold_absolute_pressure = processed_value;
                     } else {
@@ -436,12 +813,12 @@ impl SuzukiSdlViewer {
                 }
                 ScanToolParameter::VehicleSpeed => {
                     let raw_value = self.raw_data.get(&ObdAddress::VehicleSpeedSensor).unwrap();
-                    let processed_value = *raw_value;
+                    let processed_value = self.vehicle.corrected_speed(*raw_value);
                     self.engine_context.vehicle_speed = processed_value;
                 }
                 ScanToolParameter::IgnitionAdvance => {
                     let raw_value = self.raw_data.get(&ObdAddress::IgnitionAdvance).unwrap();
-                    let processed_value = Self::calculate_ignition_advance(*raw_value);
+                    let processed_value = Self::calculate_ignition_advance(profile, *raw_value);
                     self.engine_context.ignition_advance = processed_value;
                 }
                 ScanToolParameter::CalculatedLoad => {
@@ -452,7 +829,6 @@ impl SuzukiSdlViewer {
                     self.engine_context.calculated_load = processed_value.round() as u8;
                 }
                 ScanToolParameter::FuelConsumption => {
-                    let now = Instant::now();
                     if let Some(last_poll) = self.engine_context.last_poll {
                         let rpm = self.engine_context.engine_speed as f64;
                         if rpm > 0.0 {
@@ -464,11 +840,13 @@ impl SuzukiSdlViewer {
                             let duty_cycle = (inj_pw as f64) / engine_cycle_time;
 
                             // calculate fuel flow rate
-                            let actual_flow_per_injector = INJECTOR_FLOW_RATE * duty_cycle;
-                            let total_fuel_flow = actual_flow_per_injector * 4.0;
+                            let actual_flow_per_injector =
+                                self.vehicle.injector_flow_rate * duty_cycle;
+                            let total_fuel_flow =
+                                actual_flow_per_injector * self.vehicle.cylinders as f64;
                             let fuel_flow_rate_litres_per_hour = total_fuel_flow * 60.0 / 1000.0;
 
-                            let time_delta = Instant::now().duration_since(last_poll).as_secs_f64();
+                            let time_delta = now.duration_since(last_poll).as_secs_f64();
                             let fuel_flow_litres_per_second =
                                 fuel_flow_rate_litres_per_hour / 3600.0; // L/second
 
@@ -537,52 +915,76 @@ impl SuzukiSdlViewer {
         }
     }
 
+    /// Conversion constants and addresses for the connected ECU, [`G13BB`] if it isn't known.
+    pub fn parameter_profile(&self) -> &'static ParameterProfile {
+        self.ecu.map_or(&G13BB, |ecu| ecu.profile)
+    }
+
+    /// Entry for `id` in [`ecu::KNOWN_ECUS`] or [`Self::extra_ecus`].
+    pub fn lookup_ecu(&self, id: [u8; 2]) -> Option<&'static KnownEcu> {
+        ecu::lookup(id).or_else(|| self.extra_ecus.iter().find(|ecu| ecu.id == id))
+    }
+
     /// Send ID request to ECU as a means of verifying connection.
-    pub fn connect(&mut self) {
-        let ecu_id = self.get_ecu_id();
+    pub fn connect(&mut self) -> Result<(), SdlError> {
+        let ecu_id = self.get_ecu_id()?;
         self.ecu_id = Some(ecu_id);
+        self.ecu = self.lookup_ecu(ecu_id);
+        self.connection_state = ConnectionState::Streaming;
+        self.backoff.reset();
+        self.poll_scheduler.reset();
+        // time spent without the ECU must not count towards fuel used
+        self.engine_context.last_poll = None;
+        Ok(())
     }
 
-    fn calculate_tps_angle(raw: u8) -> u8 {
-        let processed_value = (raw as f32 * 125.0) / 255.0;
+    fn calculate_tps_angle(profile: &ParameterProfile, raw: u8) -> u8 {
+        let processed_value = (raw as f32 * profile.throttle_angle_max) / 255.0;
         processed_value.round() as u8
     }
 
-    fn calculate_rpm_high(raw: u8) -> u16 {
-        let processed_value = (((raw as u16) << 8) | 0u16) as f32 / 5.1;
+    #[allow(clippy::identity_op)]
+    fn calculate_rpm_high(profile: &ParameterProfile, raw: u8) -> u16 {
+        let processed_value = (((raw as u16) << 8) | 0u16) as f32 / profile.rpm_divisor;
         processed_value.round() as u16
     }
 
-    fn calculate_rpm_low(raw: u8) -> u16 {
-        let processed_value = (((0u16) << 8) | raw as u16) as f32 / 5.1;
+    #[allow(clippy::identity_op)]
+    fn calculate_rpm_low(profile: &ParameterProfile, raw: u8) -> u16 {
+        let processed_value = (((0u16) << 8) | raw as u16) as f32 / profile.rpm_divisor;
         processed_value.round() as u16
     }
 
-    fn calculate_desired_idle(raw: u8) -> u16 {
-        let processed_value = raw as f32 * 7.84375;
+    fn calculate_desired_idle(profile: &ParameterProfile, raw: u8) -> u16 {
+        let processed_value = raw as f32 * profile.desired_idle_scale;
         processed_value.round() as u16
     }
 
-    fn calculate_temps(raw: u8) -> i8 {
-        let processed_value = (raw as f32 / 255.0) * 159.0 - 40.0;
+    fn calculate_temps(profile: &ParameterProfile, raw: u8) -> i8 {
+        let (min, max) = profile.temp_range;
+        let processed_value = (raw as f32 / 255.0) * (max - min) + min;
         processed_value.round() as i8
     }
 
-    fn calculate_inj_pw_high(raw: u8) -> f32 {
-        (((raw as u16) << 8) | 0u16) as f32 * 0.002
+    #[allow(clippy::identity_op)]
+    fn calculate_inj_pw_high(profile: &ParameterProfile, raw: u8) -> f32 {
+        (((raw as u16) << 8) | 0u16) as f32 * profile.inj_pw_scale
     }
 
-    fn calculate_inj_pw_low(raw: u8) -> f32 {
-        (((0u16) << 8) | raw as u16) as f32 * 0.002
+    #[allow(clippy::identity_op)]
+    fn calculate_inj_pw_low(profile: &ParameterProfile, raw: u8) -> f32 {
+        (((0u16) << 8) | raw as u16) as f32 * profile.inj_pw_scale
     }
 
-    fn calculate_ignition_advance(raw: u8) -> i8 {
-        let processed_value = (raw as f32 / 255.0) * (78.0 - (-12.0)) + (-12.0);
+    fn calculate_ignition_advance(profile: &ParameterProfile, raw: u8) -> i8 {
+        let (min, max) = profile.ignition_advance_range;
+        let processed_value = (raw as f32 / 255.0) * (max - min) + min;
         processed_value.round() as i8
     }
 
-    fn calculate_pressure(raw: u8) -> f32 {
-        (raw as f32 / 255.0) * (146.63 - (-20.0)) + (-20.0)
+    fn calculate_pressure(profile: &ParameterProfile, raw: u8) -> f32 {
+        let (min, max) = profile.pressure_range;
+        (raw as f32 / 255.0) * (max - min) + min
     }
 
     fn calculate_ac_flag(raw: u8) -> bool {
@@ -609,16 +1011,35 @@ impl SuzukiSdlViewer {
         let processed_value = (raw as f32 / 255.0) * 100.0;
         processed_value.round() as u8
     }
-    fn calculate_battery_voltage(raw: u8) -> f32 {
-        raw as f32 * 0.0787
+    fn calculate_battery_voltage(profile: &ParameterProfile, raw: u8) -> f32 {
+        raw as f32 * profile.battery_voltage_scale
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
-    use crate::sdl::SuzukiSdlViewer;
+    use std::{
+        collections::HashMap,
+        io,
+        time::{Duration, Instant},
+    };
+
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        actuator::ActuatorTest,
+        connection::{Backoff, ConnectionState, MAX_POLL_FAILURES},
+        dtc::FaultCode,
+        ecu::G13BB,
+        emulator::SIMULATOR_ECU,
+        error::SdlError,
+        polling::PollGroup,
+        sdl::{
+            EngineContext, ObdAddress, ScanToolParameter, SdlHeader, SdlMessage, SuzukiSdlViewer,
+        },
+        toggle_detector::ToggleDetector,
+        transport::{MockTransport, UnpluggedConnector},
+    };
 
     #[test]
     fn test_rpm_high() {
@@ -635,7 +1056,7 @@ mod tests {
             (255, 12800),
         ]);
         for (key, value) in inputs {
-            assert_eq!(SuzukiSdlViewer::calculate_rpm_high(key), value)
+            assert_eq!(SuzukiSdlViewer::calculate_rpm_high(&G13BB, key), value)
         }
     }
 
@@ -654,7 +1075,7 @@ mod tests {
             (255, 50),
         ]);
         for (key, value) in inputs {
-            assert_eq!(SuzukiSdlViewer::calculate_rpm_low(key), value)
+            assert_eq!(SuzukiSdlViewer::calculate_rpm_low(&G13BB, key), value)
         }
     }
 
@@ -673,7 +1094,7 @@ mod tests {
             (255, 2000),
         ]);
         for (key, value) in inputs {
-            assert_eq!(SuzukiSdlViewer::calculate_desired_idle(key), value)
+            assert_eq!(SuzukiSdlViewer::calculate_desired_idle(&G13BB, key), value)
         }
     }
 
@@ -699,7 +1120,7 @@ mod tests {
             (255, 119),
         ]);
         for (key, value) in inputs {
-            assert_eq!(SuzukiSdlViewer::calculate_temps(key), value)
+            assert_eq!(SuzukiSdlViewer::calculate_temps(&G13BB, key), value)
         }
     }
 
@@ -718,7 +1139,7 @@ mod tests {
             (255, 125),
         ]);
         for (key, value) in inputs {
-            assert_eq!(SuzukiSdlViewer::calculate_tps_angle(key), value)
+            assert_eq!(SuzukiSdlViewer::calculate_tps_angle(&G13BB, key), value)
         }
     }
 
@@ -738,7 +1159,7 @@ mod tests {
         ]);
         for (key, value) in inputs {
             assert_eq!(
-                format!("{:.3}", SuzukiSdlViewer::calculate_inj_pw_high(key)),
+                format!("{:.3}", SuzukiSdlViewer::calculate_inj_pw_high(&G13BB, key)),
                 value
             )
         }
@@ -760,7 +1181,7 @@ mod tests {
         ]);
         for (key, value) in inputs {
             assert_eq!(
-                format!("{:.3}", SuzukiSdlViewer::calculate_inj_pw_low(key)),
+                format!("{:.3}", SuzukiSdlViewer::calculate_inj_pw_low(&G13BB, key)),
                 value
             )
         }
@@ -781,7 +1202,10 @@ mod tests {
             (255, 78),
         ]);
         for (key, value) in inputs {
-            assert_eq!(SuzukiSdlViewer::calculate_ignition_advance(key), value)
+            assert_eq!(
+                SuzukiSdlViewer::calculate_ignition_advance(&G13BB, key),
+                value
+            )
         }
     }
 
@@ -801,7 +1225,7 @@ mod tests {
         ]);
         for (key, value) in inputs {
             assert_eq!(
-                SuzukiSdlViewer::calculate_pressure(key).round() as i16,
+                SuzukiSdlViewer::calculate_pressure(&G13BB, key).round() as i16,
                 value
             )
         }
@@ -816,13 +1240,16 @@ mod tests {
 
     #[test]
     fn test_battery_voltage() {
-        assert_eq!(SuzukiSdlViewer::calculate_battery_voltage(0).round(), 0.0);
         assert_eq!(
-            SuzukiSdlViewer::calculate_battery_voltage(128).round(),
+            SuzukiSdlViewer::calculate_battery_voltage(&G13BB, 0).round(),
+            0.0
+        );
+        assert_eq!(
+            SuzukiSdlViewer::calculate_battery_voltage(&G13BB, 128).round(),
             10.0
         );
         assert_eq!(
-            SuzukiSdlViewer::calculate_battery_voltage(255).round(),
+            SuzukiSdlViewer::calculate_battery_voltage(&G13BB, 255).round(),
             20.0
         );
     }
@@ -837,6 +1264,44 @@ mod tests {
     }
 
     #[test]
+    fn test_trip_only_loaded_when_asked() {
+        let path = std::env::temp_dir().join(format!("suzui-trip-{}", std::process::id()));
+        std::fs::write(&path, "1234.5,60.25,800").unwrap();
+        let mut viewer = SuzukiSdlViewer::new(None);
+        assert_eq!(viewer.engine_context.cumulative_distance, 0.0);
+        viewer.load_trip(&path);
+        assert_eq!(viewer.engine_context.cumulative_distance, 1234.5);
+        assert_eq!(viewer.engine_context.cumulative_fuel, 60.25);
+        assert_eq!(viewer.engine_context.total_fuel_used, 800.0);
+        std::fs::remove_file(&path).unwrap();
+
+        viewer.load_trip(&path);
+        assert_eq!(viewer.engine_context.cumulative_distance, 0.0);
+    }
+
+    #[test]
+    fn test_parameters_read_through_export_channels() {
+        let context = EngineContext {
+            ignition_advance: 12,
+            fuel_flow_rate: 1.5,
+            electric_load: true,
+            ..EngineContext::default()
+        };
+        for parameter in ScanToolParameter::iter() {
+            let channel = parameter.channel();
+            assert_eq!(parameter.unit(), channel.unit());
+            assert_eq!(
+                parameter.value(&context).to_string(),
+                channel.format(&context)
+            );
+        }
+        assert_eq!(ScanToolParameter::IgnitionAdvance.unit(), "deg BTDC");
+        assert_eq!(ScanToolParameter::FuelConsumption.value(&context), 1.5);
+        assert_eq!(ScanToolParameter::ElectricLoad.value(&context), 1.0);
+    }
+
+    #[test]
+    #[allow(clippy::needless_bool)]
     fn test_status_flags() {
         let inputs = vec![1, 2, 4, 8, 16, 32, 64];
         for input in inputs {
@@ -858,4 +1323,411 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_fuel_flow_from_vehicle_profile() {
+        let mut viewer = SuzukiSdlViewer::new(None);
+        viewer.raw_data.insert(ObdAddress::RpmHigh, 0x0a);
+        viewer
+            .raw_data
+            .insert(ObdAddress::InjectorPulseWidthHigh, 0x05);
+        viewer.engine_context.last_poll = Some(Instant::now());
+        viewer.update_processed_data();
+        let four_cylinders = viewer.engine_context.fuel_flow_rate;
+        assert!(four_cylinders > 0.0);
+
+        viewer.vehicle.cylinders = 3;
+        viewer.engine_context.last_poll = Some(Instant::now());
+        viewer.update_processed_data();
+        let three_cylinders = viewer.engine_context.fuel_flow_rate;
+        assert!((three_cylinders - four_cylinders * 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_connect_reads_ecu_id() {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer.connect().unwrap();
+        assert_eq!(viewer.ecu_id, Some([0x31, 0x09]));
+        assert_eq!(mock.written(), vec![vec![0x10, 0x03, 0xed]]);
+    }
+
+    #[test]
+    fn test_connect_looks_up_ecu() {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0xde, 0xad])).to_bytes());
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0x00, 0x00])).to_bytes());
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0x00, 0x00])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        viewer.connect().unwrap();
+        assert_eq!(viewer.ecu, None);
+        assert_eq!(viewer.parameter_profile(), &G13BB);
+
+        // zeros are only the simulator when it was registered
+        viewer.connect().unwrap();
+        assert_eq!(viewer.ecu, None);
+        viewer.extra_ecus = &[SIMULATOR_ECU];
+        viewer.connect().unwrap();
+        assert_eq!(viewer.ecu.map(|ecu| ecu.model), Some("Simulator"));
+    }
+
+    #[test]
+    fn test_update_raw_data_maps_response_to_addresses() {
+        let mock = MockTransport::new();
+        let values: Vec<u8> = (1..=17).collect();
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(values)).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        viewer.update_raw_data().unwrap();
+        assert_eq!(viewer.raw_data[&ObdAddress::RpmHigh], 1);
+        assert_eq!(viewer.raw_data[&ObdAddress::RpmLow], 2);
+        assert_eq!(viewer.raw_data[&ObdAddress::StatusFlags], 17);
+        assert_eq!(viewer.raw_data[&ObdAddress::FaultCodes1], 0);
+    }
+
+    #[test]
+    fn test_update_raw_data_polls_fast_group_in_between() {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(vec![0; 17])).to_bytes());
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some((1..=10).collect())).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer.update_raw_data().unwrap();
+        viewer.update_raw_data().unwrap();
+        let fast: Vec<u8> = PollGroup::Fast
+            .addresses()
+            .iter()
+            .map(|addr| *addr as u8)
+            .collect();
+        assert_eq!(
+            mock.written()[1],
+            SdlMessage::new(SdlHeader::Data, Some(fast)).to_bytes()
+        );
+        assert_eq!(viewer.raw_data[&ObdAddress::RpmHigh], 1);
+        assert_eq!(viewer.raw_data[&ObdAddress::ManifoldAbsolutePressure], 9);
+        assert_eq!(viewer.raw_data[&ObdAddress::EngineCoolantTemperature], 0);
+    }
+
+    #[test]
+    fn test_el_toggles_seen_between_slow_polls() {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])).to_bytes());
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(vec![0; 17])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_connector(mock.clone());
+        assert_eq!(viewer.poll_ecu(), Ok(false));
+        assert_eq!(viewer.poll_ecu(), Ok(true));
+
+        // six flicks of the lights in a second and a half, all within one slow poll interval
+        let start = Instant::now();
+        let mut trip_reset = ToggleDetector::new();
+        trip_reset.update_at(viewer.engine_context.electric_load, start);
+        let mut reset = false;
+        for poll in 1..=6u32 {
+            let flags = if poll % 2 == 1 { 1 << 6 } else { 0 };
+            let mut values = vec![0; PollGroup::Fast.addresses().len()];
+            *values.last_mut().unwrap() = flags;
+            mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(values)).to_bytes());
+            assert_eq!(viewer.poll_ecu(), Ok(true));
+            viewer.update_processed_data();
+            reset = trip_reset.update_at(
+                viewer.engine_context.electric_load,
+                start + Duration::from_millis(250) * poll,
+            );
+        }
+        assert!(reset);
+    }
+
+    #[test]
+    fn test_read_fault_codes() {
+        let mock = MockTransport::new();
+        mock.push_response(
+            &SdlMessage::new(SdlHeader::Data, Some(vec![0x02, 0, 0, 0, 0, 0x01])).to_bytes(),
+        );
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        let dtcs = viewer.read_fault_codes().unwrap().clone();
+        assert_eq!(dtcs.current, vec![FaultCode::EctHigh]);
+        assert_eq!(dtcs.history, vec![FaultCode::ClosedThrottleSwitch]);
+        assert_eq!(
+            mock.written()[0],
+            SdlMessage::new(
+                SdlHeader::Data,
+                Some(vec![0x00, 0x01, 0x02, 0x03, 0x20, 0x21])
+            )
+            .to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_clear_fault_codes() {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::ClearCodes, None).to_bytes());
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(vec![0; 6])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer.fault_codes.history = vec![FaultCode::EctLow];
+        assert!(viewer.clear_fault_codes().is_ok());
+        assert!(viewer.fault_codes.is_empty());
+        assert_eq!(mock.written()[0], vec![0x14, 0x03, 0xe9]);
+    }
+
+    #[test]
+    fn test_clear_fault_codes_rejects_bad_ack() {
+        let mock = MockTransport::new();
+        mock.push_response(&[0x14, 0x03, 0x00]);
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        assert!(matches!(
+            viewer.clear_fault_codes(),
+            Err(SdlError::BadChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn test_actuator_start_stop() {
+        let mock = MockTransport::new();
+        let on = SdlMessage::new(SdlHeader::Actuate, Some(vec![0x03, 0x01])).to_bytes();
+        let off = SdlMessage::new(SdlHeader::Actuate, Some(vec![0x03, 0x00])).to_bytes();
+        mock.push_response(&on);
+        mock.push_response(&off);
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer
+            .start_actuator_test(ActuatorTest::RadiatorFan)
+            .unwrap();
+        assert_eq!(viewer.active_actuator_test, Some(ActuatorTest::RadiatorFan));
+        viewer.stop_actuator_test().unwrap();
+        assert_eq!(viewer.active_actuator_test, None);
+        assert_eq!(mock.written(), vec![on, off]);
+    }
+
+    #[test]
+    fn test_actuator_refused_while_driving() {
+        let mock = MockTransport::new();
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer.engine_context.vehicle_speed = 20;
+        assert!(
+            viewer
+                .start_actuator_test(ActuatorTest::FixedSpark)
+                .is_err()
+        );
+        assert!(mock.written().is_empty());
+    }
+
+    #[test]
+    fn test_actuator_stops_when_driving() {
+        let mock = MockTransport::new();
+        mock.push_response(&SdlMessage::new(SdlHeader::Actuate, Some(vec![0x02, 0x01])).to_bytes());
+        mock.push_response(&SdlMessage::new(SdlHeader::Actuate, Some(vec![0x02, 0x00])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer.start_actuator_test(ActuatorTest::IdleUp).unwrap();
+        viewer.engine_context.engine_speed = 3000;
+        viewer.enforce_actuator_safety().unwrap();
+        assert_eq!(viewer.active_actuator_test, None);
+        assert_eq!(mock.written().len(), 2);
+    }
+
+    #[test]
+    fn test_unanswered_stop_is_retried() {
+        let mock = MockTransport::new();
+        let off = SdlMessage::new(SdlHeader::Actuate, Some(vec![0x01, 0x00])).to_bytes();
+        mock.push_response(&SdlMessage::new(SdlHeader::Actuate, Some(vec![0x01, 0x01])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        viewer
+            .start_actuator_test(ActuatorTest::FixedSpark)
+            .unwrap();
+        viewer.engine_context.vehicle_speed = 30;
+
+        // no answer to the stop
+        assert!(viewer.enforce_actuator_safety().is_err());
+        assert_eq!(viewer.active_actuator_test, Some(ActuatorTest::FixedSpark));
+
+        mock.push_response(&off);
+        viewer.enforce_actuator_safety().unwrap();
+        assert_eq!(viewer.active_actuator_test, None);
+        assert_eq!(&mock.written()[1..], [off.clone(), off]);
+    }
+
+    #[test]
+    fn test_errors_instead_of_panics() {
+        let mut viewer = SuzukiSdlViewer::new(None);
+        assert_eq!(
+            viewer.update_raw_data(),
+            Err(SdlError::PortGone(std::io::ErrorKind::NotConnected))
+        );
+
+        let mock = MockTransport::new();
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        assert_eq!(viewer.connect(), Err(SdlError::Timeout));
+
+        mock.push_response(&[0x42, 0x05, 0x00, 0x00, 0x00]);
+        assert_eq!(viewer.connect(), Err(SdlError::BadHeader(0x42)));
+
+        mock.push_response(&[0x10, 0x09, 0x00, 0x00, 0x00]);
+        assert!(matches!(
+            viewer.connect(),
+            Err(SdlError::TruncatedFrame { .. })
+        ));
+        assert_eq!(viewer.link_stats.timeouts, 1);
+        assert_eq!(viewer.link_stats.other_frame_errors, 2);
+    }
+
+    #[test]
+    fn test_checksum_verified() {
+        let mut frame = SdlMessage::new(SdlHeader::Data, Some(vec![0x12, 0x34])).to_bytes();
+        assert!(SdlMessage::try_from(&frame[..]).is_ok());
+        frame[2] ^= 0x01;
+        assert!(matches!(
+            SdlMessage::try_from(&frame[..]),
+            Err(SdlError::BadChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_response_rejected_and_counted() {
+        let mock = MockTransport::new();
+        let mut frame = SdlMessage::new(SdlHeader::Data, Some((1..=17).collect())).to_bytes();
+        frame[3] = 0xff;
+        mock.push_response(&frame);
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        assert!(matches!(
+            viewer.update_raw_data(),
+            Err(SdlError::BadChecksum { .. })
+        ));
+        assert_eq!(viewer.raw_data[&ObdAddress::RpmHigh], 0);
+        assert_eq!(viewer.link_stats.checksum_errors, 1);
+    }
+
+    #[test]
+    fn test_echo_mismatch_rejected_and_counted() {
+        let mock = MockTransport::new();
+        // one address byte lost on the wire, still a well formed frame
+        let addrs: Vec<u8> = ObdAddress::iter()
+            .filter(|addr| !addr.is_fault_code())
+            .map(|addr| addr as u8)
+            .collect();
+        let garbled = SdlMessage::new(SdlHeader::Data, Some(addrs[1..].to_vec())).to_bytes();
+        mock.push_echo_override(&garbled);
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some((1..=17).collect())).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock.clone());
+        assert_eq!(
+            viewer.update_raw_data(),
+            Err(SdlError::EchoMismatch {
+                sent: mock.written()[0].clone(),
+                received: garbled,
+            })
+        );
+        assert_eq!(viewer.raw_data[&ObdAddress::RpmHigh], 0);
+        assert_eq!(viewer.link_stats.echo_errors, 1);
+        assert_eq!(viewer.link_stats.errors(), 1);
+    }
+
+    #[test]
+    fn test_link_stats_counted() {
+        let mock = MockTransport::new();
+        let data = SdlMessage::new(SdlHeader::Data, Some(vec![0; 17])).to_bytes();
+        let mut corrupt = data.clone();
+        corrupt[3] = 0xff;
+        mock.push_response(&data);
+        mock.push_response(&corrupt);
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        viewer.update_raw_data().unwrap();
+        assert!(viewer.update_raw_data().is_err());
+        assert!(viewer.update_raw_data().is_err());
+        assert_eq!(viewer.link_stats.polls, 1);
+        assert_eq!(viewer.link_stats.responses, 1);
+        assert_eq!(viewer.link_stats.checksum_errors, 1);
+        assert_eq!(viewer.link_stats.timeouts, 1);
+        assert!(viewer.link_stats.average_latency().is_some());
+    }
+
+    #[test]
+    fn test_response_must_match_request() {
+        let mock = MockTransport::new();
+        // well-formed, but an ID answer to a Data request
+        mock.push_response(&SdlMessage::new(SdlHeader::Id, Some(vec![0; 17])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        assert!(matches!(
+            viewer.update_raw_data(),
+            Err(SdlError::UnexpectedResponse(_))
+        ));
+        assert_eq!(viewer.link_stats.other_frame_errors, 1);
+    }
+
+    #[test]
+    fn test_wrong_length_counted_once() {
+        let mock = MockTransport::new();
+        // a Data answer, but one value short of the addresses asked for
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(vec![0; 16])).to_bytes());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        assert!(matches!(
+            viewer.update_raw_data(),
+            Err(SdlError::UnexpectedResponse(_))
+        ));
+        assert_eq!(viewer.link_stats.responses, 0);
+        assert_eq!(viewer.link_stats.other_frame_errors, 1);
+        assert_eq!(viewer.link_stats.errors(), 1);
+    }
+
+    #[test]
+    fn test_response_after_line_noise() {
+        let mock = MockTransport::new();
+        let frame = SdlMessage::new(SdlHeader::Data, Some((1..=17).collect())).to_bytes();
+        mock.push_response(&[&[0x00, 0x13, 0xfe][..], &frame].concat());
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        viewer.update_raw_data().unwrap();
+        assert_eq!(viewer.raw_data[&ObdAddress::StatusFlags], 17);
+        assert_eq!(viewer.link_stats.errors(), 0);
+    }
+
+    #[test]
+    fn test_connection_lost_and_resumed() {
+        let mock = MockTransport::new();
+        let id = SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])).to_bytes();
+        mock.push_response(&id);
+        let mut viewer = SuzukiSdlViewer::with_connector(mock.clone());
+        viewer.backoff = Backoff::new(Duration::ZERO, Duration::ZERO);
+        assert_eq!(viewer.connection_state, ConnectionState::Disconnected);
+        assert_eq!(viewer.poll_ecu(), Ok(false));
+        assert_eq!(viewer.connection_state, ConnectionState::Streaming);
+
+        // a couple of missed polls are tolerated
+        for _ in 1..MAX_POLL_FAILURES {
+            assert_eq!(viewer.poll_ecu(), Err(SdlError::Timeout));
+            assert_eq!(viewer.connection_state, ConnectionState::Streaming);
+        }
+        assert_eq!(viewer.poll_ecu(), Err(SdlError::Timeout));
+        assert_eq!(viewer.connection_state, ConnectionState::Lost);
+
+        // probing with ID requests until the ECU answers again
+        assert_eq!(viewer.poll_ecu(), Err(SdlError::Timeout));
+        assert_eq!(mock.written().last(), Some(&vec![0x10, 0x03, 0xed]));
+        mock.push_response(&id);
+        assert_eq!(viewer.poll_ecu(), Ok(false));
+        assert_eq!(viewer.connection_state, ConnectionState::Streaming);
+        mock.push_response(&SdlMessage::new(SdlHeader::Data, Some(vec![0; 17])).to_bytes());
+        assert_eq!(viewer.poll_ecu(), Ok(true));
+        assert!(viewer.last_data.is_some());
+    }
+
+    #[test]
+    fn test_unplugged_port_retried_with_backoff() {
+        let mut viewer = SuzukiSdlViewer::with_connector(UnpluggedConnector);
+        assert_eq!(
+            viewer.poll_ecu(),
+            Err(SdlError::PortGone(io::ErrorKind::NotFound))
+        );
+        assert_eq!(viewer.connection_state, ConnectionState::Disconnected);
+        // next attempt waits for the backoff
+        assert_eq!(viewer.poll_ecu(), Ok(false));
+    }
+
+    #[test]
+    fn test_request_delay_between_requests() {
+        let mock = MockTransport::new();
+        let id = SdlMessage::new(SdlHeader::Id, Some(vec![0x31, 0x09])).to_bytes();
+        mock.push_response(&id);
+        mock.push_response(&id);
+        let mut viewer = SuzukiSdlViewer::with_transport(mock);
+        viewer.request_delay = Duration::from_millis(50);
+        let start = Instant::now();
+        viewer.connect().unwrap();
+        viewer.connect().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
 }
@@ -0,0 +1,469 @@
+use std::time::Duration;
+
+use crate::{ecu::G13BB, emulator::EcuEmulator, sdl::ObdAddress};
+
+/// Road speed in km/h per 1000 RPM in each gear.
+pub const GEAR_SPEEDS: [f32; 5] = [7.5, 13.5, 20.0, 27.0, 33.0];
+
+/// Warm idle the ECU aims for with no loads on.
+pub const BASE_IDLE: f32 = 800.0;
+
+/// The model is integrated in steps no longer than this.
+const MAX_STEP: Duration = Duration::from_millis(20);
+
+/// Thermostat starts opening here, fully open 6 C above.
+const THERMOSTAT_OPEN: f32 = 82.0;
+const FAN_ON: f32 = 97.0;
+const FAN_OFF: f32 = 92.0;
+
+/// Fuel is cut on overrun above this RPM and restored below [`DFCO_RESUME`].
+const DFCO_CUT: f32 = 1500.0;
+const DFCO_RESUME: f32 = 1200.0;
+const REV_LIMIT: f32 = 6300.0;
+
+/// Rough engine and vehicle model behind `--simulate`, good enough to drive the widgets the way
+/// a car would: cold start and warm-up, idle speed control with A/C and lights loads, radiator
+/// fan cycling, fuel cut on overrun and road speed following the gear.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineSimulator {
+    /// Accelerator pedal, 0.0 to 1.0.
+    pub throttle: f32,
+
+    /// A/C switched on, raises idle and loads the engine.
+    pub ac: bool,
+
+    /// Lights on, shows up as electric load.
+    pub lights: bool,
+
+    pub ambient_temp: f32,
+    pub baro: f32,
+
+    /// Volts the battery reads below what the alternator should give, to fake a failing
+    /// charging system.
+    pub battery_drop: f32,
+
+    rpm: f32,
+    /// Road speed in km/h.
+    speed: f32,
+    /// 0 is neutral (clutch in), 1-5 otherwise.
+    gear: u8,
+    coolant_temp: f32,
+    intake_air_temp: f32,
+    isc_duty: f32,
+    fan: bool,
+    fuel_cut: bool,
+}
+
+impl Default for EngineSimulator {
+    fn default() -> Self {
+        Self::cold_start(25.0)
+    }
+}
+
+impl EngineSimulator {
+    /// Engine just started after sitting long enough to be at `ambient_temp`.
+    pub fn cold_start(ambient_temp: f32) -> Self {
+        Self {
+            throttle: 0.0,
+            ac: false,
+            lights: false,
+            ambient_temp,
+            baro: 100.0,
+            battery_drop: 0.0,
+            rpm: BASE_IDLE,
+            speed: 0.0,
+            gear: 0,
+            coolant_temp: ambient_temp,
+            intake_air_temp: ambient_temp,
+            isc_duty: 35.0,
+            fan: false,
+            fuel_cut: false,
+        }
+    }
+
+    /// Engine at operating temperature, idling.
+    pub fn warm(ambient_temp: f32) -> Self {
+        Self {
+            coolant_temp: 88.0,
+            intake_air_temp: ambient_temp + 10.0,
+            isc_duty: 25.0,
+            ..Self::cold_start(ambient_temp)
+        }
+    }
+
+    pub fn rpm(&self) -> f32 {
+        self.rpm
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn gear(&self) -> u8 {
+        self.gear
+    }
+
+    pub fn coolant_temp(&self) -> f32 {
+        self.coolant_temp
+    }
+
+    /// Force the coolant temperature, the model carries on from there.
+    pub fn set_coolant_temp(&mut self, temp: f32) {
+        self.coolant_temp = temp;
+    }
+
+    pub fn intake_air_temp(&self) -> f32 {
+        self.intake_air_temp
+    }
+
+    /// Force the intake air temperature, the model carries on from there.
+    pub fn set_intake_air_temp(&mut self, temp: f32) {
+        self.intake_air_temp = temp;
+    }
+
+    pub fn fan(&self) -> bool {
+        self.fan
+    }
+
+    pub fn fuel_cut(&self) -> bool {
+        self.fuel_cut
+    }
+
+    fn throttle_closed(&self) -> bool {
+        self.throttle < 0.01
+    }
+
+    /// Idle the ECU aims for: higher when cold, bumped up for the A/C and lights.
+    pub fn target_idle(&self) -> f32 {
+        let cold = (60.0 - self.coolant_temp).max(0.0) * 7.0;
+        let ac = if self.ac { 150.0 } else { 0.0 };
+        let lights = if self.lights { 50.0 } else { 0.0 };
+        BASE_IDLE + cold + ac + lights
+    }
+
+    /// Advance the model by `dt`.
+    pub fn step(&mut self, dt: Duration) {
+        let mut remaining = dt;
+        while !remaining.is_zero() {
+            let step = remaining.min(MAX_STEP);
+            self.integrate(step.as_secs_f32());
+            remaining -= step;
+        }
+    }
+
+    fn integrate(&mut self, dt: f32) {
+        self.idle_control(dt);
+        self.drivetrain(dt);
+        self.fuel_cut_control();
+        self.thermal(dt);
+    }
+
+    /// ISC valve is trimmed towards the target idle while the throttle is closed and the clutch
+    /// is in, otherwise it sits at the dashpot position.
+    fn idle_control(&mut self, dt: f32) {
+        if self.throttle_closed() && self.gear == 0 {
+            self.isc_duty += (self.target_idle() - self.rpm) * 0.02 * dt;
+        } else {
+            self.isc_duty += (30.0 - self.isc_duty) * dt;
+        }
+        self.isc_duty = self.isc_duty.clamp(10.0, 80.0);
+    }
+
+    fn drivetrain(&mut self, dt: f32) {
+        let throttle = if self.rpm > REV_LIMIT || self.fuel_cut {
+            0.0
+        } else {
+            self.throttle
+        };
+        if self.gear == 0 && !self.throttle_closed() {
+            self.gear = 1;
+        }
+
+        // road load: rolling resistance plus air drag, v in m/s
+        let v = self.speed / 3.6;
+        let drag = if v > 0.0 { 0.15 + 0.00045 * v * v } else { 0.0 };
+
+        if self.gear == 0 {
+            // free revving against the idle air and accessory loads
+            let loads = if self.ac { 150.0 } else { 0.0 } + if self.lights { 30.0 } else { 0.0 };
+            let free_rpm = 300.0 + self.isc_duty * 20.0 - loads;
+            self.rpm += (free_rpm - self.rpm) * (dt / 0.3).min(1.0);
+            self.speed = ((v - (drag + 0.5) * dt) * 3.6).max(0.0);
+            return;
+        }
+
+        let gear_speed = GEAR_SPEEDS[self.gear as usize - 1];
+        let accel = if throttle > 0.0 {
+            // torque falls off towards both ends of the rev range
+            let torque = 1.0 - ((self.rpm - 3500.0) / 4000.0).powi(2);
+            throttle * 3.5 * (GEAR_SPEEDS[0] / gear_speed) * torque.max(0.3)
+        } else {
+            -0.4 * (self.rpm / 3000.0)
+        };
+        self.speed = ((v + (accel - drag) * dt) * 3.6).max(0.0);
+
+        let wheel_rpm = self.speed / gear_speed * 1000.0;
+        if self.gear == 1 && wheel_rpm < 1500.0 {
+            // pulling away, clutch slipping
+            if self.throttle_closed() && self.speed < 10.0 {
+                self.gear = 0;
+                return;
+            }
+            let slip_rpm = wheel_rpm.max(1200.0 + throttle * 1500.0);
+            self.rpm += (slip_rpm - self.rpm) * (dt / 0.3).min(1.0);
+        } else {
+            self.rpm = wheel_rpm;
+        }
+
+        let upshift = 2500.0 + self.throttle * 3000.0;
+        if self.rpm > upshift && self.gear < GEAR_SPEEDS.len() as u8 && !self.throttle_closed() {
+            self.gear += 1;
+        } else if self.rpm < 1100.0 && self.gear > 1 {
+            self.gear -= 1;
+        }
+    }
+
+    /// DFCO on overrun once warm, with some hysteresis so it doesn't chatter.
+    fn fuel_cut_control(&mut self) {
+        let overrun = self.throttle_closed() && self.gear > 0 && self.coolant_temp > 40.0;
+        self.fuel_cut = if self.fuel_cut {
+            overrun && self.rpm > DFCO_RESUME
+        } else {
+            overrun && self.rpm > DFCO_CUT
+        };
+    }
+
+    fn thermal(&mut self, dt: f32) {
+        let heat = if self.fuel_cut {
+            0.02
+        } else {
+            0.05 + self.rpm / 1000.0 * (0.03 + 0.12 * self.throttle)
+        };
+        let thermostat = ((self.coolant_temp - THERMOSTAT_OPEN) / 6.0).clamp(0.0, 1.0);
+        let airflow = 0.01 + self.speed / 50.0 + if self.fan { 0.15 } else { 0.0 };
+        let over_ambient = self.coolant_temp - self.ambient_temp;
+        let cooling = over_ambient * (0.0005 + 0.02 * thermostat * airflow);
+        self.coolant_temp += (heat - cooling) * dt;
+
+        if self.coolant_temp >= FAN_ON {
+            self.fan = true;
+        } else if self.coolant_temp <= FAN_OFF {
+            self.fan = false;
+        }
+
+        // heat soak from the engine bay, blown away by road speed
+        let soak = (self.coolant_temp - self.ambient_temp) * 0.3 * (1.0 - self.speed / 60.0);
+        let iat_target = self.ambient_temp + soak.max(0.0);
+        self.intake_air_temp += (iat_target - self.intake_air_temp) * (dt / 60.0);
+    }
+
+    pub fn manifold_pressure(&self) -> f32 {
+        if self.throttle_closed() || self.fuel_cut {
+            let idle = self.baro * (0.15 + 0.006 * self.isc_duty);
+            idle * (BASE_IDLE / self.rpm).clamp(0.6, 1.0)
+        } else {
+            self.baro * (0.3 + 0.7 * self.throttle)
+        }
+    }
+
+    /// Injector pulse width in ms, zero while fuel is cut.
+    pub fn injector_pulse_width(&self) -> f32 {
+        if self.fuel_cut {
+            return 0.0;
+        }
+        let cold_enrichment = (60.0 - self.coolant_temp).max(0.0) / 60.0 * 0.4;
+        self.manifold_pressure() / self.baro * 8.0 * (1.0 + cold_enrichment)
+    }
+
+    pub fn ignition_advance(&self) -> f32 {
+        let load = self.manifold_pressure() / self.baro;
+        let advance = 8.0 + (self.rpm - BASE_IDLE).max(0.0) / 5000.0 * 28.0 * (1.0 - 0.5 * load);
+        advance.clamp(5.0, 40.0)
+    }
+
+    pub fn battery_voltage(&self) -> f32 {
+        let mut volts = 14.4 - self.battery_drop;
+        for (load, drop) in [(self.lights, 0.2), (self.fan, 0.2), (self.ac, 0.1)] {
+            if load {
+                volts -= drop;
+            }
+        }
+        volts
+    }
+
+    /// Write the current state into `ecu` memory the way the ECU would report it.
+    pub fn write_to(&self, ecu: &mut EcuEmulator) {
+        let profile = &G13BB;
+        let rpm = (self.rpm * profile.rpm_divisor).round().clamp(0.0, 65535.0) as u16;
+        let inj_pw = (self.injector_pulse_width() / profile.inj_pw_scale).round() as u16;
+        let mut flags = 0;
+        if self.throttle_closed() {
+            flags |= 1 << 4;
+        }
+        if self.ac {
+            flags |= 1 << 2;
+        }
+        if self.lights {
+            flags |= 1 << 6;
+        }
+
+        for (addr, value) in [
+            (ObdAddress::RpmHigh, (rpm >> 8) as u8),
+            (ObdAddress::RpmLow, rpm as u8),
+            (
+                ObdAddress::TargetIdle,
+                scale(self.target_idle(), profile.desired_idle_scale),
+            ),
+            (ObdAddress::VehicleSpeedSensor, scale(self.speed, 1.0)),
+            (
+                ObdAddress::EngineCoolantTemperature,
+                encode_range(self.coolant_temp, profile.temp_range),
+            ),
+            (
+                ObdAddress::IntakeAirTemperature,
+                encode_range(self.intake_air_temp, profile.temp_range),
+            ),
+            (
+                ObdAddress::TpsAngle,
+                encode_range(self.throttle * 84.0, (0.0, profile.throttle_angle_max)),
+            ),
+            (
+                ObdAddress::TpsVoltage,
+                scale(0.1 + 0.8 * self.throttle, 1.0 / 255.0),
+            ),
+            (ObdAddress::InjectorPulseWidthHigh, (inj_pw >> 8) as u8),
+            (ObdAddress::InjectorPulseWidthLow, inj_pw as u8),
+            (
+                ObdAddress::IgnitionAdvance,
+                encode_range(self.ignition_advance(), profile.ignition_advance_range),
+            ),
+            (
+                ObdAddress::ManifoldAbsolutePressure,
+                encode_range(self.manifold_pressure(), profile.pressure_range),
+            ),
+            (
+                ObdAddress::BarometricPressure,
+                encode_range(self.baro, profile.pressure_range),
+            ),
+            (ObdAddress::IscFlowDuty, scale(self.isc_duty, 100.0 / 255.0)),
+            (
+                ObdAddress::BatteryVoltage,
+                scale(self.battery_voltage(), profile.battery_voltage_scale),
+            ),
+            (ObdAddress::RadiatorFan, if self.fan { 128 } else { 0 }),
+            (ObdAddress::StatusFlags, flags),
+        ] {
+            ecu.set(addr, value);
+        }
+    }
+}
+
+/// Raw value for `value` given the size of one raw unit.
+fn scale(value: f32, per_unit: f32) -> u8 {
+    (value / per_unit).round().clamp(0.0, 255.0) as u8
+}
+
+/// Raw value for `value` on a linear scale where raw 0 and 255 are the ends of `range`, see
+/// [`crate::ecu::ParameterProfile`].
+fn encode_range(value: f32, (min, max): (f32, f32)) -> u8 {
+    ((value - min) / (max - min) * 255.0)
+        .round()
+        .clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        sdl::SuzukiSdlViewer,
+        simulator::{BASE_IDLE, EngineSimulator, GEAR_SPEEDS},
+        transport::SimulatedTransport,
+    };
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    /// Below this the thermostat is shut and coolant only ever warms up.
+    const THERMOSTAT_SHUT: f32 = 80.0;
+
+    #[test]
+    fn test_idle_follows_target() {
+        let mut sim = EngineSimulator::warm(25.0);
+        sim.step(30 * SECOND);
+        assert!((sim.rpm() - BASE_IDLE).abs() < 30.0, "{}", sim.rpm());
+
+        sim.ac = true;
+        sim.lights = true;
+        sim.step(30 * SECOND);
+        assert!(
+            (sim.rpm() - sim.target_idle()).abs() < 30.0,
+            "{}",
+            sim.rpm()
+        );
+        assert!(sim.target_idle() > BASE_IDLE);
+    }
+
+    #[test]
+    fn test_warm_up_and_fan_cycling() {
+        let mut sim = EngineSimulator::cold_start(20.0);
+        let mut previous = sim.coolant_temp();
+        let mut fan_switches = 0;
+        for _ in 0..3600 {
+            sim.step(SECOND);
+            if sim.coolant_temp() < THERMOSTAT_SHUT {
+                assert!(sim.coolant_temp() >= previous);
+            }
+            assert!(sim.coolant_temp() < 105.0);
+            if sim.fan() != (fan_switches % 2 == 1) {
+                fan_switches += 1;
+            }
+            previous = sim.coolant_temp();
+        }
+        // idling with no airflow the fan has to kick in, more than once
+        assert!(fan_switches >= 3, "{fan_switches}");
+    }
+
+    #[test]
+    fn test_speed_follows_gear_and_fuel_cut_on_overrun() {
+        let mut sim = EngineSimulator::warm(25.0);
+        sim.throttle = 0.4;
+        sim.step(20 * SECOND);
+        assert!(sim.speed() > 50.0, "{}", sim.speed());
+        assert!(sim.gear() > 1);
+        let gear_speed = GEAR_SPEEDS[sim.gear() as usize - 1];
+        assert!((sim.speed() / sim.rpm() * 1000.0 - gear_speed).abs() < 0.1);
+        assert!(!sim.fuel_cut());
+
+        sim.throttle = 0.0;
+        sim.step(SECOND);
+        assert!(sim.fuel_cut());
+        assert_eq!(sim.injector_pulse_width(), 0.0);
+
+        // rolls to a stop and idles again
+        sim.step(120 * SECOND);
+        assert_eq!(sim.speed(), 0.0);
+        assert_eq!(sim.gear(), 0);
+        assert!(!sim.fuel_cut());
+        assert!(sim.injector_pulse_width() > 0.0);
+    }
+
+    #[test]
+    fn test_decoded_values_match_model() {
+        let transport = SimulatedTransport::default();
+        let engine = transport.engine();
+        *engine.lock().unwrap() = EngineSimulator::warm(25.0);
+        let mut viewer = SuzukiSdlViewer::with_transport(transport);
+        viewer.poll_ecu().unwrap();
+        viewer.poll_ecu().unwrap();
+        viewer.update_processed_data();
+
+        let ctx = viewer.engine_context;
+        let sim = engine.lock().unwrap();
+        assert!((ctx.engine_speed as f32 - sim.rpm()).abs() < 2.0);
+        assert_eq!(ctx.coolant_temp, sim.coolant_temp().round() as i8);
+        assert!((ctx.injector_pulse_width_cyl_1 - sim.injector_pulse_width()).abs() < 0.01);
+        assert!((ctx.manifold_absolute_pressure - sim.manifold_pressure()).abs() < 1.0);
+        assert!(ctx.closed_throttle_position);
+        assert!(!ctx.fuel_cut);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    fmt,
+    io::{self, Read},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    decoder::SdlFrameDecoder,
+    sdl::{ObdAddress, SdlHeader, SdlMessage},
+};
+
+/// A frame not followed by its answer within this long is reported as unanswered.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Something seen on the K-line, timestamped relative to the start of the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// Request and the ECU response to it.
+    Exchange {
+        at: Duration,
+        request: SdlMessage,
+        response: SdlMessage,
+        latency: Duration,
+    },
+
+    /// Frame nothing answered, or an answer whose request was missed.
+    Unpaired { at: Duration, frame: SdlMessage },
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::Exchange {
+                at,
+                request,
+                response,
+                latency,
+            } => {
+                write!(
+                    f,
+                    "{:>10.3} {:<10} [{}] -> [{}] ({} ms)",
+                    at.as_secs_f64(),
+                    request.header().to_string(),
+                    format_bytes(request.data()),
+                    format_bytes(response.data()),
+                    latency.as_millis()
+                )?;
+                // spell out which value belongs to which address
+                if request.header() == SdlHeader::Data {
+                    for (addr, value) in request.data().iter().zip(response.data()) {
+                        match ObdAddress::from_repr(*addr as usize) {
+                            Some(name) => write!(f, " {name}={value}")?,
+                            None => write!(f, " {addr:#04x}={value}")?,
+                        }
+                    }
+                }
+                Ok(())
+            }
+            TraceEvent::Unpaired { at, frame } => write!(
+                f,
+                "{:>10.3} {:<10} [{}] unpaired",
+                at.as_secs_f64(),
+                frame.header().to_string(),
+                format_bytes(frame.data())
+            ),
+        }
+    }
+}
+
+/// Whether `response` can be the ECU answer to `request`.
+fn answers(request: &SdlMessage, response: &SdlMessage) -> bool {
+    if request.header() != response.header() {
+        return false;
+    }
+    match request.header() {
+        SdlHeader::Id => request.data().is_empty() && response.data().len() == 2,
+        SdlHeader::Data => request.data().len() == response.data().len(),
+        SdlHeader::ClearCodes => request.data().is_empty() && response.data().is_empty(),
+        SdlHeader::Actuate => request == response,
+    }
+}
+
+/// Listen-only decoder for traffic between some other scan tool and the ECU. Since nothing is
+/// sent, there is no echo to skip: a frame is a request and the next matching frame within
+/// [`RESPONSE_TIMEOUT`] is its response.
+#[derive(Debug)]
+pub struct Sniffer {
+    decoder: SdlFrameDecoder,
+    pending: Option<(Instant, SdlMessage)>,
+    start: Instant,
+}
+
+impl Sniffer {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            decoder: SdlFrameDecoder::new(),
+            pending: None,
+            start,
+        }
+    }
+
+    /// Bytes that didn't decode into any frame, i.e. line noise.
+    pub fn discarded(&self) -> usize {
+        self.decoder.discarded()
+    }
+
+    /// Feed bytes read off the line at `now`.
+    pub fn push(&mut self, bytes: &[u8], now: Instant) -> Vec<TraceEvent> {
+        self.decoder.push(bytes);
+        let mut events: Vec<TraceEvent> = self.expire(now).into_iter().collect();
+        while let Some(frame) = self.decoder.next_frame() {
+            match self.pending.take() {
+                Some((sent, request)) if answers(&request, &frame) => {
+                    events.push(TraceEvent::Exchange {
+                        at: sent.duration_since(self.start),
+                        request,
+                        response: frame,
+                        latency: now.duration_since(sent),
+                    });
+                }
+                previous => {
+                    if let Some((sent, request)) = previous {
+                        events.push(TraceEvent::Unpaired {
+                            at: sent.duration_since(self.start),
+                            frame: request,
+                        });
+                    }
+                    self.pending = Some((now, frame));
+                }
+            }
+        }
+        events
+    }
+
+    /// Report the pending frame as unpaired once its answer is overdue.
+    pub fn expire(&mut self, now: Instant) -> Option<TraceEvent> {
+        let (sent, _) = self.pending.as_ref()?;
+        if now.duration_since(*sent) < RESPONSE_TIMEOUT {
+            return None;
+        }
+        let (sent, frame) = self.pending.take()?;
+        Some(TraceEvent::Unpaired {
+            at: sent.duration_since(self.start),
+            frame,
+        })
+    }
+
+    /// Read from `port` forever, handing every event to `on_event`. Never writes to the port.
+    pub fn run(
+        &mut self,
+        port: &mut dyn Read,
+        mut on_event: impl FnMut(&TraceEvent),
+    ) -> io::Result<()> {
+        let mut chunk = [0u8; 64];
+        loop {
+            let events = match port.read(&mut chunk) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(len) => self.push(&chunk[..len], Instant::now()),
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    self.expire(Instant::now()).into_iter().collect()
+                }
+                Err(err) => return Err(err),
+            };
+            events.iter().for_each(&mut on_event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        sdl::{SdlHeader, SdlMessage},
+        sniffer::{RESPONSE_TIMEOUT, Sniffer, TraceEvent},
+    };
+
+    #[test]
+    fn test_pairs_requests_with_responses() {
+        let start = Instant::now();
+        let mut sniffer = Sniffer::new(start);
+        let request = SdlMessage::new(SdlHeader::Data, Some(vec![0x04, 0x05]));
+        let response = SdlMessage::new(SdlHeader::Data, Some(vec![0x12, 0x34]));
+
+        let at = start + Duration::from_millis(100);
+        assert!(sniffer.push(&request.to_bytes(), at).is_empty());
+        let events = sniffer.push(&response.to_bytes(), at + Duration::from_millis(40));
+        assert_eq!(
+            events,
+            vec![TraceEvent::Exchange {
+                at: Duration::from_millis(100),
+                request,
+                response,
+                latency: Duration::from_millis(40),
+            }]
+        );
+        assert!(events[0].to_string().contains("RpmHigh=18 RpmLow=52"));
+    }
+
+    #[test]
+    fn test_unanswered_and_mismatched_frames() {
+        let start = Instant::now();
+        let mut sniffer = Sniffer::new(start);
+        let id = SdlMessage::new(SdlHeader::Id, None);
+        let actuate = SdlMessage::new(SdlHeader::Actuate, Some(vec![0x01, 0x01]));
+
+        // ID request nobody answers, followed by an actuation the ECU acks
+        sniffer.push(&id.to_bytes(), start);
+        let events = sniffer.push(&[actuate.to_bytes(), actuate.to_bytes()].concat(), start);
+        assert!(matches!(&events[0], TraceEvent::Unpaired { frame, .. } if *frame == id));
+        assert!(matches!(&events[1], TraceEvent::Exchange { request, .. } if *request == actuate));
+
+        sniffer.push(&id.to_bytes(), start);
+        assert_eq!(sniffer.expire(start), None);
+        assert!(matches!(
+            sniffer.expire(start + RESPONSE_TIMEOUT),
+            Some(TraceEvent::Unpaired { .. })
+        ));
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::error::SdlError;
+
+/// Rolling figures (latency, poll rate) are computed over this much recent history.
+pub const STATS_WINDOW: Duration = Duration::from_secs(5);
+
+/// Counters and timings for the K-line link, to compare cables and tune timeouts.
+#[derive(Debug, Clone, Default)]
+pub struct LinkStats {
+    /// Data polls that made it into raw data.
+    pub polls: u64,
+
+    /// Requests of any kind that got a valid response.
+    pub responses: u64,
+
+    pub timeouts: u64,
+    pub checksum_errors: u64,
+    pub echo_errors: u64,
+
+    /// Bad header, truncated frame or a response that doesn't fit the request.
+    pub other_frame_errors: u64,
+
+    /// Port went away or could not be opened.
+    pub port_errors: u64,
+
+    /// Round trip of recent successful requests, from write to last byte of the response.
+    round_trips: VecDeque<(Instant, Duration)>,
+
+    /// Completion times of recent polls.
+    recent_polls: VecDeque<Instant>,
+}
+
+impl LinkStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_response(&mut self, at: Instant, round_trip: Duration) {
+        self.responses += 1;
+        self.round_trips.push_back((at, round_trip));
+        self.expire(at);
+    }
+
+    pub fn record_poll(&mut self, at: Instant) {
+        self.polls += 1;
+        self.recent_polls.push_back(at);
+        self.expire(at);
+    }
+
+    pub fn record_error(&mut self, err: &SdlError) {
+        match err {
+            SdlError::Timeout => self.timeouts += 1,
+            SdlError::BadChecksum { .. } => self.checksum_errors += 1,
+            SdlError::EchoMismatch { .. } => self.echo_errors += 1,
+            SdlError::BadHeader(_)
+            | SdlError::TruncatedFrame { .. }
+            | SdlError::UnexpectedResponse(_) => self.other_frame_errors += 1,
+            SdlError::PortGone(_) => self.port_errors += 1,
+            SdlError::Refused(_) => {}
+        }
+    }
+
+    /// Failed requests of any kind.
+    pub fn errors(&self) -> u64 {
+        self.timeouts
+            + self.checksum_errors
+            + self.echo_errors
+            + self.other_frame_errors
+            + self.port_errors
+    }
+
+    /// Mean round trip over the last [`STATS_WINDOW`].
+    pub fn average_latency(&self) -> Option<Duration> {
+        self.average_latency_at(Instant::now())
+    }
+
+    /// [`Self::average_latency`] as of `now`.
+    pub fn average_latency_at(&self, now: Instant) -> Option<Duration> {
+        let (count, total) = self
+            .round_trips_at(now)
+            .fold((0, Duration::ZERO), |(count, total), rtt| {
+                (count + 1, total + rtt)
+            });
+        (count > 0).then(|| total / count)
+    }
+
+    /// Slowest round trip over the last [`STATS_WINDOW`].
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.max_latency_at(Instant::now())
+    }
+
+    /// [`Self::max_latency`] as of `now`.
+    pub fn max_latency_at(&self, now: Instant) -> Option<Duration> {
+        self.round_trips_at(now).max()
+    }
+
+    /// Polls per second over the last [`STATS_WINDOW`].
+    pub fn poll_rate(&self) -> f32 {
+        self.poll_rate_at(Instant::now())
+    }
+
+    /// [`Self::poll_rate`] as of `now`, so the rate falls off once the ECU stops answering.
+    pub fn poll_rate_at(&self, now: Instant) -> f32 {
+        let mut polls = self
+            .recent_polls
+            .iter()
+            .filter(|at| now.duration_since(**at) <= STATS_WINDOW);
+        match (polls.next(), polls.next_back()) {
+            (Some(first), Some(last)) if first != last => {
+                (polls.count() + 1) as f32 / last.duration_since(*first).as_secs_f32()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn round_trips_at(&self, now: Instant) -> impl Iterator<Item = Duration> + '_ {
+        self.round_trips
+            .iter()
+            .filter(move |(at, _)| now.duration_since(*at) <= STATS_WINDOW)
+            .map(|(_, rtt)| *rtt)
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while self
+            .round_trips
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > STATS_WINDOW)
+        {
+            self.round_trips.pop_front();
+        }
+        while self
+            .recent_polls
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > STATS_WINDOW)
+        {
+            self.recent_polls.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{error::SdlError, stats::LinkStats};
+
+    #[test]
+    fn test_error_counters() {
+        let mut stats = LinkStats::new();
+        stats.record_error(&SdlError::Timeout);
+        stats.record_error(&SdlError::Timeout);
+        stats.record_error(&SdlError::BadChecksum {
+            expected: 0,
+            actual: 1,
+        });
+        stats.record_error(&SdlError::EchoMismatch {
+            sent: vec![],
+            received: vec![],
+        });
+        stats.record_error(&SdlError::BadHeader(0x42));
+        stats.record_error(&SdlError::Refused("test"));
+        assert_eq!(stats.timeouts, 2);
+        assert_eq!(stats.checksum_errors, 1);
+        assert_eq!(stats.echo_errors, 1);
+        assert_eq!(stats.other_frame_errors, 1);
+        assert_eq!(stats.errors(), 5);
+    }
+
+    #[test]
+    fn test_rolling_latency_and_rate() {
+        let mut stats = LinkStats::new();
+        let start = Instant::now();
+        assert_eq!(stats.average_latency(), None);
+        assert_eq!(stats.poll_rate(), 0.0);
+
+        for i in 0..=10 {
+            let at = start + Duration::from_millis(100 * i);
+            stats.record_response(at, Duration::from_millis(40 + i));
+            stats.record_poll(at);
+        }
+        let last_poll = start + Duration::from_secs(1);
+        assert_eq!(
+            stats.average_latency_at(last_poll),
+            Some(Duration::from_millis(45))
+        );
+        assert_eq!(
+            stats.max_latency_at(last_poll),
+            Some(Duration::from_millis(50))
+        );
+        assert!((stats.poll_rate_at(last_poll) - 10.0).abs() < 0.01);
+
+        // old samples fall out of the window
+        let later = start + Duration::from_secs(10);
+        stats.record_response(later, Duration::from_millis(80));
+        assert_eq!(
+            stats.average_latency_at(later),
+            Some(Duration::from_millis(80))
+        );
+        assert_eq!(stats.responses, 12);
+    }
+
+    #[test]
+    fn test_rate_drops_when_polls_stop() {
+        let mut stats = LinkStats::new();
+        let start = Instant::now();
+        for i in 0..=10 {
+            let at = start + Duration::from_millis(100 * i);
+            stats.record_response(at, Duration::from_millis(40));
+            stats.record_poll(at);
+        }
+        let last_poll = start + Duration::from_secs(1);
+        assert!((stats.poll_rate_at(last_poll) - 10.0).abs() < 0.01);
+
+        // the ECU went quiet, nothing recorded since
+        let later = last_poll + Duration::from_secs(10);
+        assert_eq!(stats.poll_rate_at(later), 0.0);
+        assert_eq!(stats.average_latency_at(later), None);
+        assert_eq!(stats.max_latency_at(later), None);
+        assert_eq!(stats.polls, 11);
+    }
+}
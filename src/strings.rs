@@ -4,4 +4,20 @@ pub const DISTANCE_FUEL_FILE_PATH: &str = "/home/dietpi/distance_fuel";
 #[cfg(not(target_arch = "aarch64"))]
 pub const DISTANCE_FUEL_FILE_PATH: &str = "/tmp/distance_fuel";
 
-pub const VAG_KKL_PORT: &str = "/dev/ttyUSB0";
+#[cfg(target_arch = "aarch64")]
+pub const CONFIG_FILE_PATH: &str = "/home/dietpi/suzui-rs.toml";
+
+#[cfg(not(target_arch = "aarch64"))]
+pub const CONFIG_FILE_PATH: &str = "/tmp/suzui-rs.toml";
+
+#[cfg(target_arch = "aarch64")]
+pub const RECORD_DIR_PATH: &str = "/home/dietpi/suzui-logs";
+
+#[cfg(not(target_arch = "aarch64"))]
+pub const RECORD_DIR_PATH: &str = "/tmp/suzui-logs";
+
+#[cfg(target_arch = "aarch64")]
+pub const FREEZE_FRAME_FILE_PATH: &str = "/home/dietpi/freeze_frames.toml";
+
+#[cfg(not(target_arch = "aarch64"))]
+pub const FREEZE_FRAME_FILE_PATH: &str = "/tmp/freeze_frames.toml";
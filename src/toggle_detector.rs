@@ -1,5 +1,7 @@
 use std::time::{Duration, Instant};
 
+use crate::page::PAGE_GESTURE_TOGGLES;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ToggleDetector {
     // last saved state.
@@ -23,6 +25,12 @@ impl ToggleDetector {
     }
 
     pub fn update(&mut self, current_el_state: bool) -> bool {
+        self.update_at(current_el_state, Instant::now())
+    }
+
+    /// [`Self::update`] with the time of the reading given, for scenario playback.
+    #[allow(clippy::collapsible_if)]
+    pub fn update_at(&mut self, current_el_state: bool, now: Instant) -> bool {
         // setup initializer value
         if self.last_state.is_none() {
             self.last_state = Some(current_el_state);
@@ -30,8 +38,6 @@ impl ToggleDetector {
         }
 
         if current_el_state != self.last_state.unwrap() {
-            let now = Instant::now();
-
             if self.first_toggle_time.is_none() {
                 self.first_toggle_time = Some(now);
                 self.toggle_count = 1;
@@ -47,7 +53,7 @@ impl ToggleDetector {
                 self.first_toggle_time = Some(now);
             }
         } else if let Some(first_time) = self.first_toggle_time {
-            if Instant::now().duration_since(first_time) > self.time_window {
+            if now.duration_since(first_time) > self.time_window {
                 self.reset_detector();
             }
         }
@@ -66,3 +72,150 @@ impl Default for ToggleDetector {
         Self::new()
     }
 }
+
+/// Counts EL toggles that follow each other closely and tells how many there were once they
+/// stop, so a quick on-off can mean something else than the trip reset's six toggles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToggleBurst {
+    last_state: Option<bool>,
+    toggle_count: u8,
+    last_toggle: Option<Instant>,
+    // quiet time that ends a burst, long enough for a slow hand on the switch.
+    gap: Duration,
+}
+
+impl ToggleBurst {
+    pub fn new() -> Self {
+        Self {
+            last_state: None,
+            toggle_count: 0,
+            last_toggle: None,
+            gap: Duration::from_secs(2),
+        }
+    }
+
+    pub fn update(&mut self, current_el_state: bool) -> Option<u8> {
+        self.update_at(current_el_state, Instant::now())
+    }
+
+    /// Number of toggles in a burst that just ended, if one did.
+    pub fn update_at(&mut self, current_el_state: bool, now: Instant) -> Option<u8> {
+        let last_state = self.last_state.replace(current_el_state);
+        let ended = match self.last_toggle {
+            Some(last_toggle) if now.duration_since(last_toggle) > self.gap => {
+                self.last_toggle = None;
+                Some(std::mem::take(&mut self.toggle_count))
+            }
+            _ => None,
+        };
+        if last_state.is_some_and(|last_state| last_state != current_el_state) {
+            self.toggle_count = self.toggle_count.saturating_add(1);
+            self.last_toggle = Some(now);
+        }
+        ended
+    }
+}
+
+impl Default for ToggleBurst {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the driver asked for with the EL switch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ElGesture {
+    NextPage,
+    TripReset,
+}
+
+/// Tells the page gesture and the trip reset apart on the same EL readings. Toggles that made up
+/// a page gesture don't count toward the trip reset, so paging a few times in a row never wipes
+/// the trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ElGestures {
+    trip_reset: ToggleDetector,
+    page: ToggleBurst,
+}
+
+impl ElGestures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, current_el_state: bool) -> Option<ElGesture> {
+        self.update_at(current_el_state, Instant::now())
+    }
+
+    /// Gesture completed by this reading, if any.
+    pub fn update_at(&mut self, current_el_state: bool, now: Instant) -> Option<ElGesture> {
+        if self.page.update_at(current_el_state, now) == Some(PAGE_GESTURE_TOGGLES) {
+            // the toggle of this reading, if there is one, starts a new count
+            self.trip_reset.reset_detector();
+            self.trip_reset.update_at(current_el_state, now);
+            return Some(ElGesture::NextPage);
+        }
+        self.trip_reset
+            .update_at(current_el_state, now)
+            .then_some(ElGesture::TripReset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::toggle_detector::{ElGesture, ElGestures, ToggleBurst};
+
+    #[test]
+    fn test_toggle_burst() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut burst = ToggleBurst::new();
+        assert_eq!(burst.update_at(false, at(0)), None);
+
+        // on and off a second apart, as EL is polled
+        assert_eq!(burst.update_at(true, at(1000)), None);
+        assert_eq!(burst.update_at(false, at(2000)), None);
+        assert_eq!(burst.update_at(false, at(4000)), None);
+        assert_eq!(burst.update_at(false, at(4001)), Some(2));
+        assert_eq!(burst.update_at(false, at(9000)), None);
+
+        // lights left on are a burst of one
+        assert_eq!(burst.update_at(true, at(10_000)), None);
+        assert_eq!(burst.update_at(true, at(13_000)), Some(1));
+
+        // a late poll still ends the burst before the new toggle counts
+        assert_eq!(burst.update_at(false, at(20_000)), None);
+        assert_eq!(burst.update_at(true, at(30_000)), Some(1));
+        assert_eq!(burst.update_at(true, at(33_000)), Some(1));
+    }
+
+    #[test]
+    fn test_page_gestures_do_not_reset_trip() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut gestures = ElGestures::new();
+        let mut seen = Vec::new();
+        let mut el = false;
+        gestures.update_at(el, at(0));
+
+        // three quick on-offs within 10 s, six toggles in all
+        for gesture in 0..3 {
+            let begin = 100 + gesture * 2500;
+            for millis in (begin..begin + 2500).step_by(100) {
+                el = millis < begin + 300;
+                seen.extend(gestures.update_at(el, at(millis)));
+            }
+        }
+        assert_eq!(seen, vec![ElGesture::NextPage; 3]);
+
+        // six toggles in one go still reset the trip, and aren't a page gesture
+        let mut seen = Vec::new();
+        for millis in (10_000..16_000).step_by(100) {
+            el = millis < 11_200 && (millis / 200) % 2 == 1;
+            seen.extend(gestures.update_at(el, at(millis)));
+        }
+        assert_eq!(seen, vec![ElGesture::TripReset]);
+    }
+}
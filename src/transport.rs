@@ -0,0 +1,260 @@
+use serialport::{ClearBuffer, SerialPort, TTYPort};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    emulator::EcuEmulator,
+    scenario::{Scenario, ScenarioPlayer},
+    sdl::{SdlHeader, SdlMessage},
+    simulator::EngineSimulator,
+};
+
+/// Anything the SDL protocol can be spoken over. The K-line is half-duplex so every byte written
+/// is expected to come back as an echo before the ECU response, transports that are not a real
+/// K-line (TCP, mock) have to emulate that echo.
+pub trait Transport: Read + Write + Debug + Send {
+    /// Drop any bytes waiting in the receive buffer, e.g. leftovers of a timed out response.
+    fn clear_input(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Real serial device (VAG KKL cable) or one side of a pseudo-terminal pair.
+impl Transport for TTYPort {
+    fn clear_input(&mut self) -> io::Result<()> {
+        self.clear(ClearBuffer::Input)?;
+        Ok(())
+    }
+}
+
+impl Transport for TcpStream {}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn clear_input(&mut self) -> io::Result<()> {
+        (**self).clear_input()
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    responses: VecDeque<Vec<u8>>,
+    echo_overrides: VecDeque<Vec<u8>>,
+    rx: VecDeque<u8>,
+    written: Vec<Vec<u8>>,
+}
+
+/// In-memory transport that answers each write with the next scripted response. Writes are
+/// echoed back like on the K-line. Clones share the same state so a test can keep a handle for
+/// inspection after handing the transport to [`crate::sdl::SuzukiSdlViewer`].
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue raw bytes to be sent back after the echo of the next write.
+    pub fn push_response(&self, bytes: &[u8]) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .push_back(bytes.to_vec());
+    }
+
+    /// Echo `bytes` back for the next write instead of what it sent, like a garbled echo on the
+    /// K-line.
+    pub fn push_echo_override(&self, bytes: &[u8]) {
+        self.state
+            .lock()
+            .unwrap()
+            .echo_overrides
+            .push_back(bytes.to_vec());
+    }
+
+    /// Every write made so far, in order.
+    pub fn written(&self) -> Vec<Vec<u8>> {
+        self.state.lock().unwrap().written.clone()
+    }
+}
+
+impl Read for MockTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.rx.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no scripted data"));
+        }
+        let len = buf.len().min(state.rx.len());
+        for (dst, src) in buf.iter_mut().zip(state.rx.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+}
+
+impl Write for MockTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        state.written.push(buf.to_vec());
+        let echo = state
+            .echo_overrides
+            .pop_front()
+            .unwrap_or_else(|| buf.to_vec());
+        state.rx.extend(echo);
+        if let Some(response) = state.responses.pop_front() {
+            state.rx.extend(response);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for MockTransport {
+    fn clear_input(&mut self) -> io::Result<()> {
+        self.state.lock().unwrap().rx.clear();
+        Ok(())
+    }
+}
+
+/// Connector whose device is never there, for tests of the no-port paths.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct UnpluggedConnector;
+
+#[cfg(test)]
+impl crate::connection::Connector for UnpluggedConnector {
+    fn open(&mut self) -> io::Result<Box<dyn Transport>> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+/// Transport used by `--simulate`. An [`EcuEmulator`] answers requests with raw values from an
+/// [`EngineSimulator`], which is advanced in real time before every Data request, following a
+/// [`Scenario`] if one is given. Fault code bytes stay at zero.
+#[derive(Debug, Default)]
+pub struct SimulatedTransport {
+    ecu: EcuEmulator,
+    engine: Arc<Mutex<EngineSimulator>>,
+    scenario: Option<Arc<Mutex<ScenarioPlayer>>>,
+    /// Fixed time to advance by per Data request instead of the real time since the last one.
+    tick: Option<Duration>,
+    last_step: Option<Instant>,
+    rx: VecDeque<u8>,
+}
+
+impl SimulatedTransport {
+    /// Play `scenario` from the first request on.
+    pub fn with_scenario(scenario: Scenario) -> Self {
+        Self {
+            engine: Arc::new(Mutex::new(scenario.engine())),
+            scenario: Some(Arc::new(Mutex::new(ScenarioPlayer::new(scenario)))),
+            ..Self::default()
+        }
+    }
+
+    /// Advance by `tick` on every Data request, including the first, rather than in real time.
+    /// Plays a scenario through as fast as it is polled.
+    pub fn with_tick(mut self, tick: Duration) -> Self {
+        self.tick = Some(tick);
+        self
+    }
+
+    /// Handle on the engine model, for throttle, A/C and lights controls.
+    pub fn engine(&self) -> Arc<Mutex<EngineSimulator>> {
+        self.engine.clone()
+    }
+
+    /// Handle on the scenario being played, if any.
+    pub fn scenario(&self) -> Option<Arc<Mutex<ScenarioPlayer>>> {
+        self.scenario.clone()
+    }
+
+    fn respond(&mut self, request: &SdlMessage) -> Option<SdlMessage> {
+        if request.header() == SdlHeader::Data {
+            let now = Instant::now();
+            let mut engine = self.engine.lock().unwrap();
+            let dt = self.tick.or_else(|| {
+                self.last_step
+                    .map(|last_step| now.duration_since(last_step))
+            });
+            if let Some(dt) = dt {
+                match &self.scenario {
+                    Some(scenario) => scenario.lock().unwrap().advance(dt, &mut engine),
+                    None => engine.step(dt),
+                }
+            }
+            engine.write_to(&mut self.ecu);
+            self.last_step = Some(now);
+        }
+        self.ecu.respond(request)
+    }
+}
+
+impl Read for SimulatedTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.rx.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no response"));
+        }
+        let len = buf.len().min(self.rx.len());
+        for (dst, src) in buf.iter_mut().zip(self.rx.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+}
+
+impl Write for SimulatedTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.rx.extend(buf);
+        if let Ok(request) = SdlMessage::try_from(buf)
+            && let Some(response) = self.respond(&request)
+        {
+            self.rx.extend(response.to_bytes());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transport for SimulatedTransport {
+    fn clear_input(&mut self) -> io::Result<()> {
+        self.rx.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use crate::transport::MockTransport;
+
+    #[test]
+    fn test_mock_echo_then_response() {
+        let mut mock = MockTransport::new();
+        let handle = mock.clone();
+        mock.push_response(&[0x10, 0x05, 0x12, 0x34, 0xa5]);
+        mock.write_all(&[0x10, 0x03, 0xed]).unwrap();
+
+        let mut buf = [0u8; 8];
+        mock.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x10, 0x03, 0xed, 0x10, 0x05, 0x12, 0x34, 0xa5]);
+        assert_eq!(handle.written(), vec![vec![0x10, 0x03, 0xed]]);
+        assert!(mock.read(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    actuator::{ActuatorTest, actuation_allowed},
+    error::SdlError,
+    sdl::SuzukiSdlViewer,
+};
+
+pub struct ActuatorPage<'a> {
+    active: Option<ActuatorTest>,
+    allowed: bool,
+    message: Option<&'a SdlError>,
+}
+
+impl<'a> ActuatorPage<'a> {
+    pub fn new(viewer: &SuzukiSdlViewer, message: Option<&'a SdlError>) -> Self {
+        Self {
+            active: viewer.active_actuator_test,
+            allowed: actuation_allowed(&viewer.engine_context),
+            message,
+        }
+    }
+}
+
+impl Widget for ActuatorPage<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        Block::new()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                "ACTIVE TESTS",
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        let mut lines: Vec<Line> = ActuatorTest::iter()
+            .enumerate()
+            .map(|(idx, test)| {
+                let style = if self.active == Some(test) {
+                    Style::default().fg(Color::Black).bg(Color::Green)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                Line::styled(format!("{}: {}", idx + 1, test.description()), style)
+            })
+            .collect();
+        lines.push(Line::from(""));
+        if !self.allowed {
+            lines.push(Line::styled(
+                "Refused: vehicle moving or engine above idle",
+                Style::default().fg(Color::Red),
+            ));
+        }
+        if let Some(message) = self.message {
+            lines.push(Line::styled(
+                message.to_string(),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        lines.push(Line::from("1-5: toggle test, s: stop, a: back"));
+        Paragraph::new(lines)
+            .bold()
+            .render(area.inner(Margin::new(1, 1)), buf);
+    }
+}
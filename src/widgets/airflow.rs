@@ -1,17 +1,22 @@
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Gauge},
 };
 
-use crate::sdl::EngineContext;
+use crate::{
+    alert::{LUGGING_LOAD, LUGGING_RPM},
+    sdl::EngineContext,
+    widgets::Blink,
+};
 
 pub struct AirflowBlock {
     map: f32,
     baro: f32,
     calc_load: u8,
     rpm: u16,
+    now: Instant,
 }
 
 impl AirflowBlock {
@@ -21,25 +26,36 @@ impl AirflowBlock {
             baro: ctx.barometric_pressure,
             calc_load: ctx.calculated_load,
             rpm: ctx.engine_speed,
+            now: Instant::now(),
         }
     }
+
+    /// Blink as of `now` instead of the wall clock, for scenario playback.
+    pub fn at(mut self, now: Instant) -> Self {
+        self.now = now;
+        self
+    }
 }
 
 #[derive(Debug)]
 pub struct AirflowBlockState {
-    pub is_red: bool,
-    pub last_blink: Option<Instant>,
+    pub blink: Blink,
 }
 
 impl AirflowBlockState {
     pub fn new() -> Self {
         Self {
-            is_red: false,
-            last_blink: None,
+            blink: Blink::default(),
         }
     }
 }
 
+impl Default for AirflowBlockState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StatefulWidget for AirflowBlock {
     type State = AirflowBlockState;
 
@@ -86,32 +102,8 @@ impl StatefulWidget for AirflowBlock {
                 * 100.0) as u16
         };
         let gauge_color = match self.rpm {
-            rpm if rpm < 2500 && self.calc_load >= 85 => {
-                let color;
-                if state.last_blink.is_some() {
-                    if Instant::now().duration_since(state.last_blink.unwrap())
-                        > Duration::from_millis(500)
-                    {
-                        color = if state.is_red {
-                            Color::Black
-                        } else {
-                            Color::Red
-                        };
-                        state.is_red = !state.is_red;
-                        state.last_blink = Some(Instant::now());
-                    } else {
-                        color = if state.is_red {
-                            Color::Red
-                        } else {
-                            Color::Black
-                        };
-                    }
-                } else {
-                    state.last_blink = Some(Instant::now());
-                    state.is_red = true;
-                    color = Color::Red
-                }
-                color
+            rpm if rpm < LUGGING_RPM && self.calc_load >= LUGGING_LOAD => {
+                state.blink.color(self.now)
             }
             _ => Color::White,
         };
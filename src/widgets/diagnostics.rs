@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    connection::ConnectionState,
+    ecu::{KnownEcu, format_ecu_id},
+    sdl::SuzukiSdlViewer,
+    stats::LinkStats,
+};
+
+/// Link statistics for comparing cables and tuning timing. Not listed anywhere on the dashboard.
+pub struct DiagnosticsPage<'a> {
+    state: ConnectionState,
+    ecu_id: Option<String>,
+    ecu: Option<&'static KnownEcu>,
+    stats: &'a LinkStats,
+}
+
+impl<'a> DiagnosticsPage<'a> {
+    pub fn new(viewer: &'a SuzukiSdlViewer) -> Self {
+        Self {
+            state: viewer.connection_state,
+            ecu_id: viewer.ecu_id.map(format_ecu_id),
+            ecu: viewer.ecu,
+            stats: &viewer.link_stats,
+        }
+    }
+}
+
+fn format_latency(latency: Option<Duration>) -> String {
+    latency.map_or("-".to_string(), |latency| {
+        format!("{:.1} ms", latency.as_secs_f64() * 1000.0)
+    })
+}
+
+impl Widget for DiagnosticsPage<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        Block::new()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                "DIAGNOSTICS",
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        let stats = self.stats;
+        let rows = [
+            ("Connection", self.state.to_string()),
+            ("ECU ID", self.ecu_id.as_deref().unwrap_or("-").to_string()),
+            (
+                "ECU",
+                match (self.ecu, &self.ecu_id) {
+                    (Some(ecu), _) => format!("{} ({})", ecu.model, ecu.profile.engine_code),
+                    (None, Some(_)) => "unknown, G13BB assumed".to_string(),
+                    (None, None) => "-".to_string(),
+                },
+            ),
+            ("Poll rate", format!("{:.1} Hz", stats.poll_rate())),
+            ("Latency avg", format_latency(stats.average_latency())),
+            ("Latency max", format_latency(stats.max_latency())),
+            ("Polls", stats.polls.to_string()),
+            ("Responses", stats.responses.to_string()),
+            ("Timeouts", stats.timeouts.to_string()),
+            ("Checksum errors", stats.checksum_errors.to_string()),
+            ("Echo errors", stats.echo_errors.to_string()),
+            ("Other bad frames", stats.other_frame_errors.to_string()),
+            ("Port errors", stats.port_errors.to_string()),
+        ];
+        let mut lines: Vec<Line> = rows
+            .into_iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{label:<18}"), Style::default().fg(Color::White)),
+                    Span::styled(value, Style::default().fg(Color::Yellow)),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from("d: back"));
+        Paragraph::new(lines)
+            .bold()
+            .render(area.inner(Margin::new(1, 1)), buf);
+    }
+}
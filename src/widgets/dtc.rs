@@ -0,0 +1,169 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    dtc::{ClearCodesCountdown, FaultCode},
+    sdl::SuzukiSdlViewer,
+    widgets::centered_rect,
+};
+
+pub struct DtcBlock {
+    current: Vec<FaultCode>,
+}
+
+impl DtcBlock {
+    pub fn new(viewer: &SuzukiSdlViewer) -> Self {
+        Self {
+            current: viewer.fault_codes.current.clone(),
+        }
+    }
+}
+
+impl Widget for DtcBlock {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        if self.current.is_empty() {
+            return;
+        }
+        Block::new()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title(Span::styled(
+                "CHECK ENGINE",
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        let lines: Vec<Line> = self
+            .current
+            .iter()
+            .map(|code| Line::from(code.to_string()))
+            .collect();
+        Paragraph::new(lines)
+            .white()
+            .bold()
+            .render(area.inner(Margin::new(1, 1)), buf);
+    }
+}
+
+pub struct ClearCodesPopup {
+    codes: Vec<FaultCode>,
+    remaining_secs: u64,
+}
+
+impl ClearCodesPopup {
+    pub fn new(countdown: &ClearCodesCountdown) -> Self {
+        Self {
+            codes: countdown.codes.clone(),
+            remaining_secs: countdown.remaining().as_secs_f32().ceil() as u64,
+        }
+    }
+}
+
+impl Widget for ClearCodesPopup {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let height = self.codes.len() as u16 + 5;
+        let popup_area = centered_rect(area, 40, height);
+        Clear.render(popup_area, buf);
+        Block::new()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::LightYellow))
+            .title(Span::styled(
+                "HISTORY CODES",
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(popup_area, buf);
+        let mut lines: Vec<Line> = self
+            .codes
+            .iter()
+            .map(|code| Line::from(code.to_string()))
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Clearing in {}s", self.remaining_secs)));
+        lines.push(Line::from("x: keep codes, enter: clear now"));
+        Paragraph::new(lines)
+            .white()
+            .bold()
+            .render(popup_area.inner(Margin::new(1, 1)), buf);
+    }
+}
+
+/// Every code the ECU reports, current and stored, with descriptions.
+pub struct DtcPage {
+    current: Vec<FaultCode>,
+    history: Vec<FaultCode>,
+}
+
+impl DtcPage {
+    pub fn new(viewer: &SuzukiSdlViewer) -> Self {
+        Self {
+            current: viewer.fault_codes.current.clone(),
+            history: viewer.fault_codes.history.clone(),
+        }
+    }
+}
+
+fn code_lines(codes: &[FaultCode]) -> Vec<Line<'static>> {
+    if codes.is_empty() {
+        return vec![Line::from("None")];
+    }
+    codes
+        .iter()
+        .map(|code| Line::from(code.to_string()))
+        .collect()
+}
+
+impl Widget for DtcPage {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+        let current_color = if self.current.is_empty() {
+            Color::Black
+        } else {
+            Color::Red
+        };
+        Paragraph::new(code_lines(&self.current))
+            .white()
+            .bold()
+            .block(
+                Block::new().borders(Borders::ALL).title(Span::styled(
+                    "CURRENT CODES",
+                    Style::default()
+                        .fg(Color::White)
+                        .bg(current_color)
+                        .add_modifier(Modifier::BOLD),
+                )),
+            )
+            .render(layout[0], buf);
+        Paragraph::new(code_lines(&self.history))
+            .white()
+            .bold()
+            .block(
+                Block::new().borders(Borders::ALL).title(Span::styled(
+                    "HISTORY CODES",
+                    Style::default()
+                        .fg(Color::White)
+                        .bg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )),
+            )
+            .render(layout[1], buf);
+    }
+}
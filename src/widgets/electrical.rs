@@ -3,7 +3,7 @@ use ratatui::{
     widgets::{Block, Borders},
 };
 
-use crate::sdl::EngineContext;
+use crate::{alert::CHARGING_VOLTAGE, sdl::EngineContext};
 
 pub struct ElectricalBlock {
     battery_voltage: f32,
@@ -44,7 +44,7 @@ impl Widget for ElectricalBlock {
             .split(area.inner(Margin::new(1, 0)));
         let battery_color = match self.rpm {
             rpm if rpm > 0 => match self.battery_voltage {
-                batt_volt if !(13.1..=15.2).contains(&batt_volt) => (Color::Red, Color::White),
+                batt_volt if !CHARGING_VOLTAGE.contains(&batt_volt) => (Color::Red, Color::White),
                 _ => (Color::Black, Color::White),
             },
             _ => match self.battery_voltage {
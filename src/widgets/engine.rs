@@ -3,20 +3,22 @@ use ratatui::{
     widgets::{Block, Borders, Gauge},
 };
 
-use crate::sdl::EngineContext;
+use crate::{sdl::EngineContext, vehicle::VehicleProfile};
 
 pub struct EngineSpeedBlock {
     rpm: u16,
     desired_idle: u16,
     isc: u8,
+    redline: u16,
 }
 
 impl EngineSpeedBlock {
-    pub fn new(ctx: &EngineContext) -> Self {
+    pub fn new(ctx: &EngineContext, vehicle: &VehicleProfile) -> Self {
         Self {
             rpm: ctx.engine_speed,
             desired_idle: ctx.desired_idle,
             isc: ctx.isc_flow_duty,
+            redline: vehicle.redline,
         }
     }
 }
@@ -46,12 +48,13 @@ impl Widget for EngineSpeedBlock {
                 Constraint::Length(1), // footer
             ])
             .split(area.inner(Margin::new(1, 0)));
-        let engine_rpm_percentage = ((self.rpm as f64 / 6500_f64) * 100.0).min(100.0) as u16;
+        let engine_rpm_percentage =
+            ((self.rpm as f64 / self.redline as f64) * 100.0).min(100.0) as u16;
         let engine_rpm_color = match self.rpm {
             rpm if rpm < 500 => Color::Red,
             rpm if rpm < 2500 => Color::White,
-            rpm if rpm < 5500 => Color::Green,
-            rpm if rpm < 6500 => Color::LightYellow,
+            rpm if rpm < self.redline.saturating_sub(1000) => Color::Green,
+            rpm if rpm < self.redline => Color::LightYellow,
             _ => Color::Red,
         };
         Gauge::default()
@@ -0,0 +1,89 @@
+use std::time::Instant;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{explorer::RawExplorer, sdl::ObdAddress};
+
+/// Every ECU address as a 16x16 hex grid, with the selected and pinned addresses spelled out in
+/// hex, decimal and binary.
+pub struct RawExplorerPage<'a> {
+    explorer: &'a RawExplorer,
+}
+
+impl<'a> RawExplorerPage<'a> {
+    pub fn new(explorer: &'a RawExplorer) -> Self {
+        Self { explorer }
+    }
+
+    fn detail(&self, addr: u8) -> Line<'static> {
+        let name = ObdAddress::from_repr(addr as usize)
+            .map(|addr| addr.to_string())
+            .unwrap_or_default();
+        let value = match self.explorer.value(addr) {
+            Some(value) => format!("{value:#04x} {value:>3} {value:#010b}"),
+            None => "--".to_string(),
+        };
+        Line::from(format!("{addr:#04x} {value}  {name}"))
+    }
+}
+
+impl Widget for RawExplorerPage<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        Block::new()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                "RAW EXPLORER",
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Length(54), Constraint::Min(0)])
+            .split(area.inner(Margin::new(1, 1)));
+
+        let now = Instant::now();
+        let mut grid = vec![Line::from(
+            "      0  1  2  3  4  5  6  7  8  9  a  b  c  d  e  f",
+        )];
+        for row in 0..16u8 {
+            let mut spans = vec![Span::from(format!("{:#04x} ", row << 4))];
+            for col in 0..16u8 {
+                let addr = (row << 4) | col;
+                let mut style = Style::default().fg(Color::White);
+                if self.explorer.pinned().any(|pinned| pinned == addr) {
+                    style = style.fg(Color::Cyan);
+                }
+                if self.explorer.recently_changed(addr, now) {
+                    style = style.fg(Color::Black).bg(Color::Yellow);
+                }
+                if addr == self.explorer.cursor {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                let value = self
+                    .explorer
+                    .value(addr)
+                    .map_or("--".to_string(), |value| format!("{value:02x}"));
+                spans.push(Span::from(" "));
+                spans.push(Span::styled(value, style));
+            }
+            grid.push(Line::from(spans));
+        }
+        Paragraph::new(grid).bold().render(layout[0], buf);
+
+        let mut lines = vec![self.detail(self.explorer.cursor), Line::from("")];
+        lines.push(Line::styled("PINNED", Style::default().fg(Color::Cyan)));
+        lines.extend(self.explorer.pinned().map(|addr| self.detail(addr)));
+        lines.push(Line::from(""));
+        lines.push(Line::from("arrows: move, p: pin, e: back"));
+        Paragraph::new(lines).bold().render(layout[1], buf);
+    }
+}
@@ -0,0 +1,138 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    ecu::format_ecu_id,
+    export::format_utc,
+    freeze_frame::{FreezeFrame, FreezeFrameStore, Trigger},
+    sdl::{ObdAddress, ScanToolParameter},
+};
+
+/// Captured freeze frames, newest first, next to every value of the selected one and the raw
+/// bytes it was worked out from.
+pub struct FreezeFramePage<'a> {
+    store: &'a FreezeFrameStore,
+}
+
+impl<'a> FreezeFramePage<'a> {
+    pub fn new(store: &'a FreezeFrameStore) -> Self {
+        Self { store }
+    }
+}
+
+fn trigger_color(trigger: Trigger) -> Color {
+    match trigger {
+        Trigger::Overheat | Trigger::Battery | Trigger::Lugging => Color::Red,
+        Trigger::Manual => Color::Cyan,
+    }
+}
+
+fn values(frame: &FreezeFrame) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::styled(
+            frame.trigger.to_string(),
+            Style::default().fg(trigger_color(frame.trigger)),
+        ),
+        Line::from(format_utc(frame.at)),
+        Line::from(format!(
+            "ECU {}",
+            frame.ecu_id.map(format_ecu_id).unwrap_or_default()
+        )),
+        Line::from(""),
+    ];
+    lines.extend(ScanToolParameter::iter().map(|parameter| {
+        let value = parameter.value(&frame.context);
+        let value = match parameter.unit() {
+            "on/off" if value > 0.0 => "ON".to_string(),
+            "on/off" => "OFF".to_string(),
+            unit => format!("{value:.1} {unit}"),
+        };
+        Line::from(format!("{:<17}{value}", parameter.label()))
+    }));
+    lines
+}
+
+fn raw_data(frame: &FreezeFrame) -> Vec<Line<'static>> {
+    frame
+        .raw_data
+        .iter()
+        .map(|(addr, value)| {
+            let name = ObdAddress::from_repr(*addr as usize)
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            Line::from(format!("{addr:#04x} {value:#04x} {value:>3}  {name}"))
+        })
+        .collect()
+}
+
+impl Widget for FreezeFramePage<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        Block::new()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                format!("FREEZE FRAMES ({})", self.store.len()),
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Min(0),    // frames
+                Constraint::Length(1), // keys
+            ])
+            .split(area.inner(Margin::new(1, 1)));
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Length(36), // list
+                Constraint::Length(32), // values
+                Constraint::Min(0),     // raw data
+            ])
+            .split(rows[0]);
+
+        // keep the selected frame in view
+        let height = layout[0].height as usize;
+        let skip = (self.store.selected + 1).saturating_sub(height);
+        let list: Vec<Line> = self
+            .store
+            .frames()
+            .enumerate()
+            .skip(skip)
+            .map(|(index, frame)| {
+                let mut style = Style::default().fg(trigger_color(frame.trigger));
+                if index == self.store.selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Line::styled(
+                    format!("{:<9}{}", frame.trigger.to_string(), format_utc(frame.at)),
+                    style,
+                )
+            })
+            .collect();
+        if list.is_empty() {
+            Paragraph::new("None").render(layout[0], buf);
+        } else {
+            Paragraph::new(list).bold().render(layout[0], buf);
+        }
+
+        if let Some(frame) = self.store.selected_frame() {
+            Paragraph::new(values(frame)).bold().render(layout[1], buf);
+            Paragraph::new(raw_data(frame))
+                .style(Style::default().fg(Color::Gray))
+                .render(layout[2], buf);
+        }
+
+        Paragraph::new("up/down: frame  m: capture now")
+            .style(Style::default().fg(Color::Gray))
+            .render(rows[1], buf);
+    }
+}
@@ -3,7 +3,7 @@ use ratatui::{
     widgets::{Block, Borders, Gauge, Paragraph},
 };
 
-use crate::sdl::EngineContext;
+use crate::{sdl::EngineContext, vehicle::VehicleProfile};
 
 pub struct FuelIgnitionBlock {
     inj_pw: f32,
@@ -11,16 +11,18 @@ pub struct FuelIgnitionBlock {
     ignition_advance: i8,
     fuel_used: f64,
     fuel_flow_rate: f64,
+    tank_size: f64,
 }
 
 impl FuelIgnitionBlock {
-    pub fn new(ctx: &EngineContext) -> Self {
+    pub fn new(ctx: &EngineContext, vehicle: &VehicleProfile) -> Self {
         Self {
             inj_pw: ctx.injector_pulse_width_cyl_1,
             fuel_cut: ctx.fuel_cut,
             ignition_advance: ctx.ignition_advance,
             fuel_used: ctx.total_fuel_used,
             fuel_flow_rate: ctx.fuel_flow_rate,
+            tank_size: vehicle.tank_size,
         }
     }
 }
@@ -84,8 +86,8 @@ impl Widget for FuelIgnitionBlock {
             .bold()
             .render(row_two[0], buf);
         Paragraph::new(format!(
-            "{:.1} ({:.1})",
-            self.fuel_used, self.fuel_flow_rate
+            "{:.1}/{:.0} ({:.1})",
+            self.fuel_used, self.tank_size, self.fuel_flow_rate
         ))
         .white()
         .bold()
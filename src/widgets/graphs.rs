@@ -0,0 +1,193 @@
+use ratatui::{
+    prelude::*,
+    symbols::Marker,
+    widgets::{
+        Axis, Block, Borders, Chart, Dataset, GraphType, LegendPosition, Paragraph, Sparkline,
+    },
+};
+
+use crate::{history::History, sdl::ScanToolParameter};
+
+const PRIMARY_COLOR: Color = Color::Yellow;
+const OVERLAY_COLOR: Color = Color::Cyan;
+
+/// Rolling chart of one channel with optionally a second on top, each scaled to its own range
+/// so e.g. RPM and injector pulse width share the plot. A sparkline per channel underneath keeps
+/// the current value in view.
+pub struct GraphsPage<'a> {
+    history: &'a History,
+}
+
+impl<'a> GraphsPage<'a> {
+    pub fn new(history: &'a History) -> Self {
+        Self { history }
+    }
+}
+
+/// A channel's points within the window along with their range.
+struct Series {
+    parameter: ScanToolParameter,
+    points: Vec<(f64, f64)>,
+    min: f64,
+    max: f64,
+}
+
+impl Series {
+    fn new(history: &History, parameter: ScanToolParameter) -> Self {
+        let points = history.series(parameter);
+        let (min, max) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, y)| {
+                (min.min(*y), max.max(*y))
+            });
+        // a flat line sits in the middle rather than on the axis
+        let (min, max) = match (min, max) {
+            (min, max) if min > max => (0.0, 1.0),
+            (min, max) if min == max => (min - 1.0, max + 1.0),
+            range => range,
+        };
+        Self {
+            parameter,
+            points,
+            min,
+            max,
+        }
+    }
+
+    /// Points scaled to 0..1 of the channel's range.
+    fn normalized(&self) -> Vec<(f64, f64)> {
+        self.points
+            .iter()
+            .map(|(x, y)| (*x, (y - self.min) / (self.max - self.min)))
+            .collect()
+    }
+
+    fn current(&self) -> Option<f64> {
+        self.points.last().map(|(_, y)| *y)
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "{} {:.1}..{:.1} {}",
+            self.parameter.label(),
+            self.min,
+            self.max,
+            self.parameter.unit()
+        )
+    }
+
+    /// One bar per column, the newest sample falling in each slice of the window.
+    fn bars(&self, width: u16, window: f64) -> Vec<u64> {
+        let width = width as usize;
+        let mut bars = vec![0; width];
+        for (x, y) in &self.points {
+            let column = (((window + x) / window) * width as f64) as usize;
+            // the bottom of the range still shows as a sliver, an empty column means no data
+            let scaled = (y - self.min) / (self.max - self.min) * 90.0 + 10.0;
+            bars[column.min(width.saturating_sub(1))] = scaled.round() as u64;
+        }
+        bars
+    }
+}
+
+impl Widget for GraphsPage<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let window = self.history.window().as_secs_f64();
+        Block::new()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                format!("GRAPHS - LAST {}S", window as u64),
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Min(0),    // chart
+                Constraint::Length(3), // primary sparkline
+                Constraint::Length(3), // overlay sparkline
+                Constraint::Length(1), // keys
+            ])
+            .split(area.inner(Margin::new(1, 1)));
+
+        let primary = Series::new(self.history, self.history.primary);
+        let overlay = self
+            .history
+            .overlay
+            .map(|parameter| Series::new(self.history, parameter));
+
+        let series: Vec<(&Series, Color)> = [(Some(&primary), PRIMARY_COLOR)]
+            .into_iter()
+            .chain([(overlay.as_ref(), OVERLAY_COLOR)])
+            .filter_map(|(series, color)| series.map(|series| (series, color)))
+            .collect();
+        let points: Vec<Vec<(f64, f64)>> = series
+            .iter()
+            .map(|(series, _)| series.normalized())
+            .collect();
+        let datasets = series
+            .iter()
+            .zip(&points)
+            .map(|((series, color), points)| {
+                Dataset::default()
+                    .name(series.name())
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(*color))
+                    .data(points)
+            })
+            .collect();
+        let x_labels = vec![
+            Span::from(format!("-{}s", window as u64)),
+            Span::from(format!("-{}s", window as u64 / 2)),
+            Span::from("now"),
+        ];
+        let y_labels = vec![
+            Span::from(format!("{:.1}", primary.min)),
+            Span::from(format!("{:.1}", primary.max)),
+        ];
+        Chart::new(datasets)
+            .x_axis(
+                Axis::default()
+                    .bounds([-window, 0.0])
+                    .labels(x_labels)
+                    .style(Style::default().fg(Color::Gray)),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, 1.0])
+                    .labels(y_labels)
+                    .style(Style::default().fg(PRIMARY_COLOR)),
+            )
+            .legend_position(Some(LegendPosition::TopLeft))
+            .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
+            .render(layout[0], buf);
+
+        for ((series, color), area) in series.iter().zip([layout[1], layout[2]]) {
+            let current = series
+                .current()
+                .map(|value| format!("{value:.1} {}", series.parameter.unit()))
+                .unwrap_or_else(|| "--".to_string());
+            Sparkline::default()
+                .block(
+                    Block::new()
+                        .borders(Borders::TOP)
+                        .title(format!("{}: {current}", series.parameter.label())),
+                )
+                .data(series.bars(area.width, window))
+                .max(100)
+                .style(Style::default().fg(*color))
+                .render(area, buf);
+        }
+
+        Paragraph::new("tab/shift-tab: channel  o: overlay  w: window")
+            .style(Style::default().fg(Color::Gray))
+            .render(layout[3], buf);
+    }
+}
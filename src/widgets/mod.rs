@@ -1,8 +1,66 @@
+pub mod actuator;
 pub mod airflow;
+pub mod diagnostics;
+pub mod dtc;
 pub mod electrical;
 pub mod engine;
+pub mod explorer;
 pub mod flags;
+pub mod freeze_frame;
 pub mod fuel_ignition;
+pub mod graphs;
+pub mod status;
 pub mod temperature;
 pub mod throttle;
+pub mod trip;
 pub mod vehicle;
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Color,
+};
+
+/// Alert colour flips between red and black this often.
+pub const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Blinking red of a value out of range, kept across frames in the widget state.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Blink {
+    is_red: bool,
+    last_blink: Option<Instant>,
+}
+
+impl Blink {
+    /// Colour to draw with at `now`, starting on red.
+    pub fn color(&mut self, now: Instant) -> Color {
+        match self.last_blink {
+            Some(last_blink) if now.duration_since(last_blink) <= BLINK_INTERVAL => {}
+            Some(_) => {
+                self.is_red = !self.is_red;
+                self.last_blink = Some(now);
+            }
+            None => {
+                self.is_red = true;
+                self.last_blink = Some(now);
+            }
+        }
+        if self.is_red {
+            Color::Red
+        } else {
+            Color::Black
+        }
+    }
+}
+
+/// Area of `width` x `height` centered inside `area`, used for popups.
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
@@ -0,0 +1,219 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    connection::ConnectionState, error::SdlError, replay::Replay, sdl::SuzukiSdlViewer,
+    widgets::centered_rect,
+};
+
+/// One line bar along the bottom of the screen showing the last SDL error, if any, or else why
+/// recording or exporting stopped.
+pub struct StatusBar<'a> {
+    error: Option<&'a SdlError>,
+    recorder_error: Option<&'a io::Error>,
+    export_error: Option<&'a io::Error>,
+    freeze_frame_error: Option<&'a io::Error>,
+}
+
+impl<'a> StatusBar<'a> {
+    pub fn new(error: Option<&'a SdlError>) -> Self {
+        Self {
+            error,
+            recorder_error: None,
+            export_error: None,
+            freeze_frame_error: None,
+        }
+    }
+
+    pub fn recorder_error(mut self, error: Option<&'a io::Error>) -> Self {
+        self.recorder_error = error;
+        self
+    }
+
+    pub fn export_error(mut self, error: Option<&'a io::Error>) -> Self {
+        self.export_error = error;
+        self
+    }
+
+    pub fn freeze_frame_error(mut self, error: Option<&'a io::Error>) -> Self {
+        self.freeze_frame_error = error;
+        self
+    }
+}
+
+impl Widget for StatusBar<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let message = match (
+            self.error,
+            self.recorder_error,
+            self.export_error,
+            self.freeze_frame_error,
+        ) {
+            (Some(error), _, _, _) => format!("SDL: {error}"),
+            (None, Some(error), _, _) => format!("Recording stopped: {error}"),
+            (None, None, Some(error), _) => format!("Export stopped: {error}"),
+            (None, None, None, Some(error)) => format!("Freeze frames: {error}"),
+            (None, None, None, None) => return,
+        };
+        if area.height == 0 {
+            return;
+        }
+        let bar = Rect::new(area.x, area.bottom() - 1, area.width, 1);
+        Paragraph::new(message)
+            .white()
+            .bold()
+            .bg(Color::Red)
+            .render(bar, buf);
+    }
+}
+
+/// One line bar along the bottom of the screen with where a replay is and its controls. Drawn
+/// before the [`StatusBar`] so an error still shows over it.
+pub struct ReplayBar<'a> {
+    replay: &'a Replay,
+}
+
+impl<'a> ReplayBar<'a> {
+    pub fn new(replay: &'a Replay) -> Self {
+        Self { replay }
+    }
+}
+
+fn format_hms(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+impl Widget for ReplayBar<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        if area.height == 0 {
+            return;
+        }
+        let state = if self.replay.is_paused() {
+            "PAUSED"
+        } else if self.replay.is_finished() {
+            "END"
+        } else {
+            ""
+        };
+        let message = format!(
+            "REPLAY {} / {} {}x {state:6} space: pause  \u{2190}/\u{2192}: seek  [/]: speed  ,/.: step",
+            format_hms(self.replay.position()),
+            format_hms(self.replay.duration()),
+            self.replay.speed(),
+        );
+        let bar = Rect::new(area.x, area.bottom() - 1, area.width, 1);
+        Paragraph::new(message)
+            .black()
+            .bold()
+            .bg(Color::Cyan)
+            .render(bar, buf);
+    }
+}
+
+/// Shown over the dashboard whenever the ECU isn't streaming. Everything underneath is dimmed so
+/// the last known values read as stale.
+pub struct NoEcuOverlay {
+    state: ConnectionState,
+    last_data: Option<Instant>,
+}
+
+impl NoEcuOverlay {
+    pub fn new(viewer: &SuzukiSdlViewer) -> Self {
+        Self {
+            state: viewer.connection_state,
+            last_data: viewer.last_data,
+        }
+    }
+}
+
+impl Widget for NoEcuOverlay {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        if self.state.is_streaming() {
+            return;
+        }
+        buf.set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+        let detail = match self.state {
+            ConnectionState::Disconnected => "Cable not connected",
+            ConnectionState::Probing => "Waiting for ECU",
+            ConnectionState::Lost => "ECU stopped answering",
+            ConnectionState::Streaming => unreachable!(),
+        };
+        let stale = match self.last_data {
+            Some(last_data) => format!("Last data {}s ago", last_data.elapsed().as_secs()),
+            None => "No data yet".to_string(),
+        };
+        let popup = centered_rect(area, 30, 5);
+        Clear.render(popup, buf);
+        Paragraph::new(vec![Line::from(detail), Line::from(stale)])
+            .centered()
+            .bold()
+            .block(
+                Block::new().borders(Borders::ALL).title(Span::styled(
+                    "NO ECU",
+                    Style::default()
+                        .fg(Color::White)
+                        .bg(Color::Red)
+                        .add_modifier(Modifier::BOLD),
+                )),
+            )
+            .render(popup, buf);
+    }
+}
+
+/// Warning for an ECU ID that isn't in the database, since every value on screen is converted
+/// with G13BB constants that may not apply.
+pub struct UnknownEcuPopup<'a> {
+    ecu_id: &'a str,
+}
+
+impl<'a> UnknownEcuPopup<'a> {
+    pub fn new(ecu_id: &'a str) -> Self {
+        Self { ecu_id }
+    }
+}
+
+impl Widget for UnknownEcuPopup<'_> {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        let popup = centered_rect(area, 40, 6);
+        Clear.render(popup, buf);
+        Paragraph::new(vec![
+            Line::from(format!("ECU ID {}", self.ecu_id)),
+            Line::from("Showing G13BB values, may be wrong"),
+            Line::from(""),
+            Line::from("u: dismiss"),
+        ])
+        .centered()
+        .bold()
+        .block(
+            Block::new().borders(Borders::ALL).title(Span::styled(
+                "UNKNOWN ECU",
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        )
+        .render(popup, buf);
+    }
+}
@@ -1,34 +1,37 @@
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Gauge},
 };
 
-use crate::sdl::EngineContext;
+use crate::{alert::OVERHEAT_COOLANT_TEMP, sdl::EngineContext, widgets::Blink};
 
 #[derive(Debug)]
 pub struct TemperatureState {
-    is_iat_red: bool,
-    iat_last_blink: Option<Instant>,
-    is_ect_red: bool,
-    ect_last_blink: Option<Instant>,
+    iat_blink: Blink,
+    ect_blink: Blink,
 }
 
 impl TemperatureState {
     pub fn new() -> Self {
         Self {
-            is_iat_red: false,
-            iat_last_blink: None,
-            is_ect_red: false,
-            ect_last_blink: None,
+            iat_blink: Blink::default(),
+            ect_blink: Blink::default(),
         }
     }
 }
 
+impl Default for TemperatureState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct TemperatureBlock {
     coolant: i8,
     intake: i8,
+    now: Instant,
 }
 
 impl TemperatureBlock {
@@ -36,8 +39,15 @@ impl TemperatureBlock {
         Self {
             coolant: ctx.coolant_temp,
             intake: ctx.intake_air_temperature,
+            now: Instant::now(),
         }
     }
+
+    /// Blink as of `now` instead of the wall clock, for scenario playback.
+    pub fn at(mut self, now: Instant) -> Self {
+        self.now = now;
+        self
+    }
 }
 
 impl StatefulWidget for TemperatureBlock {
@@ -111,34 +121,8 @@ impl StatefulWidget for TemperatureBlock {
         let coolant_color = match self.coolant {
             temp if temp < 83 => Color::Blue,
             temp if temp < 101 => Color::Green,
-            temp if temp < 110 => Color::LightYellow,
-            _ => {
-                let color;
-                if state.ect_last_blink.is_some() {
-                    if Instant::now().duration_since(state.ect_last_blink.unwrap())
-                        > Duration::from_millis(500)
-                    {
-                        color = if state.is_ect_red {
-                            Color::Black
-                        } else {
-                            Color::Red
-                        };
-                        state.is_ect_red = !state.is_ect_red;
-                        state.ect_last_blink = Some(Instant::now());
-                    } else {
-                        color = if state.is_ect_red {
-                            Color::Red
-                        } else {
-                            Color::Black
-                        };
-                    }
-                } else {
-                    state.ect_last_blink = Some(Instant::now());
-                    state.is_ect_red = true;
-                    color = Color::Red
-                }
-                color
-            }
+            temp if temp < OVERHEAT_COOLANT_TEMP => Color::LightYellow,
+            _ => state.ect_blink.color(self.now),
         };
         Gauge::default()
             .percent(coolant_percentage)
@@ -172,33 +156,7 @@ impl StatefulWidget for TemperatureBlock {
             iat if iat < 20 => Color::Blue,
             iat if iat < 50 => Color::Green,
             iat if iat < 70 => Color::Yellow,
-            _ => {
-                let color;
-                if state.iat_last_blink.is_some() {
-                    if Instant::now().duration_since(state.iat_last_blink.unwrap())
-                        > Duration::from_millis(500)
-                    {
-                        color = if state.is_iat_red {
-                            Color::Black
-                        } else {
-                            Color::Red
-                        };
-                        state.is_iat_red = !state.is_iat_red;
-                        state.iat_last_blink = Some(Instant::now());
-                    } else {
-                        color = if state.is_iat_red {
-                            Color::Red
-                        } else {
-                            Color::Black
-                        };
-                    }
-                } else {
-                    state.iat_last_blink = Some(Instant::now());
-                    state.is_iat_red = true;
-                    color = Color::Red
-                }
-                color
-            }
+            _ => state.iat_blink.color(self.now),
         };
         Gauge::default()
             .percent(intake_percentage)
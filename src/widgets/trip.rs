@@ -0,0 +1,82 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{sdl::EngineContext, vehicle::VehicleProfile};
+
+/// Trip meter totals since the last reset, in large print for a glance while driving.
+pub struct TripPage {
+    distance: f64,
+    cumulative_fuel: f64,
+    total_fuel_used: f64,
+    fuel_consumption: f64,
+    instant_consumption: f64,
+    fuel_flow_rate: f64,
+    displacement: f64,
+    cylinders: u8,
+}
+
+impl TripPage {
+    pub fn new(ctx: &EngineContext, vehicle: &VehicleProfile) -> Self {
+        Self {
+            distance: ctx.cumulative_distance,
+            cumulative_fuel: ctx.cumulative_fuel,
+            total_fuel_used: ctx.total_fuel_used,
+            fuel_consumption: ctx.fuel_consumption,
+            instant_consumption: ctx.instant_consumption,
+            fuel_flow_rate: ctx.fuel_flow_rate,
+            displacement: vehicle.displacement,
+            cylinders: vehicle.cylinders,
+        }
+    }
+}
+
+impl Widget for TripPage {
+    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
+    where
+        Self: Sized,
+    {
+        Block::new()
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                "TRIP COMPUTER",
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+        // fuel used while standing still, i.e. idling
+        let idle_fuel = (self.total_fuel_used - self.cumulative_fuel).max(0.0);
+        let rows = [
+            ("Distance", format!("{:.1} km", self.distance)),
+            (
+                "Average consumption",
+                format!("{:.1} L/100km", self.fuel_consumption),
+            ),
+            (
+                "Instant consumption",
+                format!("{:.1} L/100km", self.instant_consumption),
+            ),
+            ("Fuel flow", format!("{:.2} L/h", self.fuel_flow_rate)),
+            ("Fuel used", format!("{:.2} L", self.total_fuel_used)),
+            ("  while moving", format!("{:.2} L", self.cumulative_fuel)),
+            ("  while idling", format!("{idle_fuel:.2} L")),
+            (
+                "Engine",
+                format!("{:.1} L {} cyl", self.displacement, self.cylinders),
+            ),
+        ];
+        let mut lines: Vec<Line> = rows
+            .into_iter()
+            .map(|(label, value)| Line::from(format!("{label:<22}{value:>14}")))
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from("Toggle EL 6 times in 10 s to reset").gray());
+        Paragraph::new(lines)
+            .white()
+            .bold()
+            .render(area.inner(Margin::new(2, 1)), buf);
+    }
+}